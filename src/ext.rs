@@ -31,7 +31,7 @@ impl FloatExt for f32 {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// Tracks how many buttons are being pressed in specific directions
 pub struct InputState {
     /// Buttons in the left-right direction
@@ -53,14 +53,14 @@ impl InputState {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct MouseDown {
     pub left: bool,
     pub middle: bool,
     pub right: bool,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Modifiers {
     pub ctrl: bool,
     pub shift: bool,
@@ -0,0 +1,192 @@
+//! Finding cover from the player
+//!
+//! Tiles around an enemy are scored by how close they are and whether the player
+//! could shoot into them. The best spots have an open tile next to them to peek out of.
+use std::cmp::Ordering;
+
+use crate::{
+    util::{Point2, Vector2},
+    obj::decoration::{DecorationObj, line_of_sight},
+};
+use super::{
+    world::Grid,
+    path::{WalkMap, tile_center},
+};
+
+/// How many tiles away from the enemy cover is looked for
+const SEARCH_RADIUS: u16 = 6;
+/// Added to the score of cover that can't be peeked out of
+const NO_PEEK_PENALTY: f32 = 96.;
+/// Half the width of a body that has to be hidden
+const BODY: f32 = 12.;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CoverSpot {
+    /// Where the threat can't shoot
+    pub cover: Point2,
+    /// A neighbouring spot from which the threat can be shot
+    pub peek: Option<Point2>,
+}
+
+/// Finds the best reachable cover from a threat near a position
+///
+/// Cover has to be reachable without leaving the area searched.
+pub fn find_cover(grid: &Grid, decorations: &[DecorationObj], walkable: &WalkMap, from: Point2, threat: Point2) -> Option<CoverSpot> {
+    let reachable = walkable.reachable(Grid::snap(from), SEARCH_RADIUS);
+
+    let mut candidates = Vec::new();
+    for tile in reachable {
+        if !walkable.is_walkable(tile) {
+            continue
+        }
+        let cover = tile_center(tile);
+        if !is_hidden(grid, decorations, threat, cover) {
+            continue
+        }
+        let spot = CoverSpot {
+            cover,
+            peek: peek_spot(grid, decorations, walkable, tile, threat),
+        };
+        candidates.push((score(spot, from, threat), spot));
+    }
+    candidates.into_iter()
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+        .map(|(_, spot)| spot)
+}
+
+/// Lower is better
+fn score(spot: CoverSpot, from: Point2, threat: Point2) -> f32 {
+    let walk = (spot.cover - from).norm();
+    // Not too close to the threat either
+    let distance = (spot.cover - threat).norm().min(256.);
+    let peek = if spot.peek.is_some() { 0. } else { NO_PEEK_PENALTY };
+
+    walk - 0.5 * distance + peek
+}
+
+/// Whether a body at a position is out of the line of fire
+fn is_hidden(grid: &Grid, decorations: &[DecorationObj], threat: Point2, pos: Point2) -> bool {
+    let dist = pos - threat;
+    let len = dist.norm();
+    if len <= std::f32::EPSILON {
+        return false;
+    }
+    let side = BODY / len * Vector2::new(-dist.y, dist.x);
+
+    [Vector2::new(0., 0.), side, -side].iter()
+        .all(|&off| !line_of_sight(grid, decorations, threat, dist + off))
+}
+
+/// A neighbouring tile from which the threat can be seen
+fn peek_spot(grid: &Grid, decorations: &[DecorationObj], walkable: &WalkMap, (x, y): (u16, u16), threat: Point2) -> Option<Point2> {
+    let mut best: Option<Point2> = None;
+    for dx in -1i32..=1 {
+        for dy in -1i32..=1 {
+            let nx = i32::from(x) + dx;
+            let ny = i32::from(y) + dy;
+            // Only straight neighbours, peeking diagonally would cut corners
+            if (dx == 0) == (dy == 0) || nx < 0 || ny < 0 {
+                continue
+            }
+            let tile = (nx as u16, ny as u16);
+            if !walkable.is_walkable(tile) {
+                continue
+            }
+            let peek = tile_center(tile);
+            if line_of_sight(grid, decorations, threat, peek - threat) {
+                // Peek from as far away as possible
+                let further = best.map(|b| (peek - threat).norm() > (b - threat).norm()).unwrap_or(true);
+                if further {
+                    best = Some(peek);
+                }
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A floor with a single wall tile at `(4, 2)`, the threat stands at `(1, 2)`
+    const PILLAR: &[&str] = &[
+        ".........",
+        ".........",
+        "....#....",
+        ".........",
+        ".........",
+    ];
+
+    fn grid(rows: &[&str]) -> (Grid, WalkMap) {
+        let grid = Grid::from_rows(rows).unwrap();
+        let walkable = WalkMap::new(&grid, &[]);
+        (grid, walkable)
+    }
+
+    #[test]
+    fn hidden_behind_a_pillar() {
+        let (grid, _) = grid(PILLAR);
+        let threat = tile_center((1, 2));
+
+        assert!(is_hidden(&grid, &[], threat, tile_center((5, 2))));
+        assert!(is_hidden(&grid, &[], threat, tile_center((7, 2))));
+        assert!(!is_hidden(&grid, &[], threat, tile_center((5, 0))));
+        assert!(!is_hidden(&grid, &[], threat, tile_center((3, 2))));
+        // Standing on the threat hides nothing
+        assert!(!is_hidden(&grid, &[], threat, threat));
+    }
+
+    #[test]
+    fn peeking_out_from_behind_a_pillar() {
+        let (grid, walkable) = grid(PILLAR);
+        let threat = tile_center((1, 2));
+
+        let peek = peek_spot(&grid, &[], &walkable, (5, 2), threat);
+        assert!(peek == Some(tile_center((5, 1))) || peek == Some(tile_center((5, 3))), "{:?}", peek);
+        // Too deep in the shadow to see the threat from any neighbour
+        assert_eq!(peek_spot(&grid, &[], &walkable, (8, 2), threat), None);
+    }
+
+    #[test]
+    fn cover_behind_a_pillar() {
+        let (grid, walkable) = grid(PILLAR);
+        let threat = tile_center((1, 2));
+
+        let spot = find_cover(&grid, &[], &walkable, tile_center((5, 0)), threat).unwrap();
+        assert!(spot.cover.x > 5. * 32., "{:?}", spot);
+        assert!(is_hidden(&grid, &[], threat, spot.cover));
+        let peek = spot.peek.unwrap();
+        assert!(line_of_sight(&grid, &[], threat, peek - threat));
+        assert!((peek - spot.cover).norm() <= 32.);
+    }
+
+    #[test]
+    fn no_cover_in_the_open() {
+        let (grid, walkable) = grid(&[
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+        ]);
+
+        assert_eq!(find_cover(&grid, &[], &walkable, tile_center((6, 1)), tile_center((2, 2))), None);
+    }
+
+    #[test]
+    fn no_cover_out_of_reach() {
+        // The enemy and the threat are shut in a room, outside of it is hidden but can't be walked to
+        let (grid, walkable) = grid(&[
+            ".........",
+            ".#######.",
+            ".#.....#.",
+            ".#.....#.",
+            ".#######.",
+            ".........",
+        ]);
+        let threat = tile_center((6, 3));
+
+        assert!(is_hidden(&grid, &[], threat, tile_center((0, 0))));
+        assert_eq!(find_cover(&grid, &[], &walkable, tile_center((2, 2)), threat), None);
+    }
+}
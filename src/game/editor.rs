@@ -1,10 +1,11 @@
 use crate::{
-    util::{TRANS, BLUE, GREEN,
+    util::{TRANS, BLUE, GREEN, RED,
+    angle_to_vec,
     Vector2, Point2},
     io::tex::{Sprite, PosText},
     io::snd::Sound,
     ext::BoolExt,
-    obj::{Object, enemy::Enemy, decoration::{DecorationObj, DECORATIONS}, pickup::PICKUPS, weapon::WEAPONS}
+    obj::{Object, enemy::{Enemy, Waypoint}, skill::Skill, archetype::ARCHETYPES, decoration::{DecorationObj, DECORATIONS}, pickup::PICKUPS, weapon::WEAPONS}
 };
 use ggez::{
     Context, GameResult,
@@ -30,10 +31,12 @@ enum Tool {
 enum Insertion {
     Material(Material),
     Intel,
-    Enemy{rot: f32},
+    Enemy{archetype: usize, rot: f32},
     Pickup(u8),
     Weapon(u8),
     Decoration{i: usize, rot: f32},
+    /// Patrol waypoints of the enemy with this index
+    Waypoint{enemy: usize, rot: f32},
     Exit,
 }
 impl ::std::cmp::PartialEq for Insertion {
@@ -42,10 +45,11 @@ impl ::std::cmp::PartialEq for Insertion {
         match (self, rhs) {
             (Material(m), Material(n)) if m == n => true,
             (Intel, Intel) => true,
-            (Enemy{..}, Enemy{..}) => true,
+            (Enemy{archetype: i, ..}, Enemy{archetype: j, ..}) if i == j => true,
             (Pickup(i), Pickup(j)) if i == j => true,
             (Weapon(i), Weapon(j)) if i == j => true,
             (Decoration{i, ..}, Decoration{i: j, ..}) if i == j => true,
+            (Waypoint{enemy: i, ..}, Waypoint{enemy: j, ..}) if i == j => true,
             (Exit, Exit) => true,
             _ => false
         }
@@ -73,11 +77,12 @@ pub struct Editor {
     entities_bar: InsertionBar,
     extra_bar: InsertionBar,
     draw_visibility_cones: bool,
+    draw_patrol_routes: bool,
     rotation_speed: f32,
     snap_on_grid: bool,
 }
 
-const PALETTE: [Material; 9] = [
+const PALETTE: [Material; 12] = [
     Material::Grass,
     Material::Dirt,
     Material::Floor,
@@ -87,18 +92,21 @@ const PALETTE: [Material; 9] = [
     Material::Concrete,
     Material::WoodFloor,
     Material::Stairs,
+    Material::WoodWall,
+    Material::Glass,
+    Material::Crate,
 ];
 
 struct InsertionBar {
     ent_text: PosText,
-    palette: &'static [EntityItem]
+    palette: Vec<EntityItem>
 }
 
 type EntityItem = (Sprite, Insertion);
 
 impl InsertionBar {
     #[allow(clippy::new_ret_no_self)]
-    fn new(p: Point2, ctx: &mut Context, s: &State, text: &str, palette: &'static [EntityItem]) -> GameResult<Self> {
+    fn new(p: Point2, ctx: &mut Context, s: &State, text: &str, palette: Vec<EntityItem>) -> GameResult<Self> {
         let ent_text = s.assets.text(ctx, p, text)?;
         Ok(Self {
             ent_text,
@@ -113,7 +121,7 @@ impl InsertionBar {
             .. Default::default()
         };
 
-        for (spr, ins) in self.palette {
+        for (spr, ins) in &self.palette {
             if let Some(cur) = cur {
                 if ins == &cur {
                     graphics::set_color(ctx, YELLOW)?;
@@ -128,7 +136,7 @@ impl InsertionBar {
     fn click(&self, mouse: Point2) -> Option<Insertion> {
         if mouse.y >= self.ent_text.pos.y && mouse.y < self.ent_text.pos.y+32. {
             let mut range = self.ent_text.pos.x + 82.;
-            for (_, ins) in self.palette {
+            for (_, ins) in &self.palette {
                 if mouse.x >= range && mouse.x < range + 32. {
                     return Some(*ins);
                 }
@@ -143,37 +151,22 @@ impl Editor {
     #[allow(clippy::new_ret_no_self)]
     pub fn new(ctx: &mut Context, s: &State, level: Option<Level>) -> GameResult<Box<dyn GameState>> {
         let mat_text = s.assets.text(ctx, Point2::new(2., 18.0), "Materials:")?;
-        let entities_bar = InsertionBar::new(Point2::new(392., 18.0), ctx, s, "Entitites:", &[
-            (Sprite::Enemy, Insertion::Enemy{rot: 0.}),
+        let mut entities: Vec<_> = ARCHETYPES.iter()
+            .enumerate()
+            .map(|(i, a)| (a.spr, Insertion::Enemy{archetype: i, rot: 0.}))
+            .collect();
+        entities.extend_from_slice(&[
             (Sprite::Goal, Insertion::Exit),
             (Sprite::Intel, Insertion::Intel),
-            (Sprite::HealthPack, Insertion::Pickup(0)),
-            (Sprite::Armour, Insertion::Pickup(1)),
-            (Sprite::Adrenaline, Insertion::Pickup(2)),
-            (Sprite::Glock, Insertion::Weapon(0)),
-            (Sprite::FiveSeven, Insertion::Weapon(1)),
-            (Sprite::Magnum, Insertion::Weapon(2)),
-            (Sprite::M4, Insertion::Weapon(3)),
-            (Sprite::Ak47, Insertion::Weapon(4)),
-            (Sprite::Arwp, Insertion::Weapon(5)),
-            (Sprite::Chair1, Insertion::Decoration{i: 0, rot: 0.}),
-            (Sprite::Chair2, Insertion::Decoration{i: 1, rot: 0.}),
-            (Sprite::ChairBoss, Insertion::Decoration{i: 2, rot: 0.}),
-            (Sprite::LampPost, Insertion::Decoration{i: 3, rot: 0.}),
-            (Sprite::OfficePlant, Insertion::Decoration{i: 4, rot: 0.}),
-            (Sprite::OfficePlant2, Insertion::Decoration{i: 5, rot: 0.}),
-            (Sprite::OfficePlant3, Insertion::Decoration{i: 6, rot: 0.}),
-            (Sprite::Trashcan, Insertion::Decoration{i: 7, rot: 0.}),
-        ])?;
-        let extra_bar = InsertionBar::new(Point2::new(392., 52.0), ctx, s, "", &[
-            (Sprite::ManholeCover, Insertion::Decoration{i: 8, rot: 0.}),
-            (Sprite::ManholeCover2, Insertion::Decoration{i: 9, rot: 0.}),
-            (Sprite::DeskLamp, Insertion::Decoration{i: 10, rot: 0.}),
-            (Sprite::WallLight, Insertion::Decoration{i: 11, rot: 0.}),
-            (Sprite::WallLight2, Insertion::Decoration{i: 12, rot: 0.}),
-            (Sprite::WallLight3, Insertion::Decoration{i: 13, rot: 0.}),
-            (Sprite::RoadMark, Insertion::Decoration{i: 14, rot: 0.}),
-        ])?;
+        ]);
+        entities.extend(PICKUPS.iter().enumerate().map(|(i, p)| (p.spr, Insertion::Pickup(i as u8))));
+        entities.extend(WEAPONS.iter().enumerate().map(|(i, w)| (w.entity_sprite, Insertion::Weapon(i as u8))));
+        let entities_bar = InsertionBar::new(Point2::new(392., 18.0), ctx, s, "Entitites:", entities)?;
+        let decorations = DECORATIONS.iter()
+            .enumerate()
+            .map(|(i, d)| (d.spr, Insertion::Decoration{i, rot: 0.}))
+            .collect();
+        let extra_bar = InsertionBar::new(Point2::new(392., 52.0), ctx, s, "", decorations)?;
 
         let save;
         if let Content::File(ref f) = s.content {
@@ -194,6 +187,7 @@ impl Editor {
             pos: Point2::new(x, y),
             current: Tool::Selector(Selection::default()),
             draw_visibility_cones: false,
+            draw_patrol_routes: false,
             mat_text,
             entities_bar,
             extra_bar,
@@ -202,6 +196,22 @@ impl Editor {
             snap_on_grid: false,
         }))
     }
+    /// Index of the enemy whose patrol route is being edited, if it's still there
+    fn editing_patrol(&self) -> Option<usize> {
+        match self.current {
+            Tool::Inserter(Insertion::Waypoint{enemy, ..}) if enemy < self.level.enemies.len() => Some(enemy),
+            _ => None,
+        }
+    }
+    /// Goes back to selecting if the current tool refers to objects by their index
+    ///
+    /// Has to be done whenever the objects of the level are replaced.
+    fn forget_indices(&mut self) {
+        match self.current {
+            Tool::Selector(_) | Tool::Inserter(Insertion::Waypoint{..}) => self.current = Tool::Selector(Selection::default()),
+            _ => (),
+        }
+    }
     fn mousepos(&self, s: &State) -> Point2 {
         let mut mp = s.mouse - s.offset;
         if self.snap_on_grid {
@@ -213,6 +223,8 @@ impl Editor {
 }
 
 const START_X: f32 = 103.;
+/// Materials per row of the palette
+const PALETTE_COLUMNS: usize = 6;
 const YELLOW: Color = Color{r: 1., g: 1., b: 0., a: 1.};
 
 impl GameState for Editor {
@@ -222,8 +234,9 @@ impl GameState for Editor {
         self.pos += v * DELTA;
 
         match self.current {
-            Tool::Inserter(Insertion::Enemy{ref mut rot}) => *rot += self.rotation_speed * DELTA,
+            Tool::Inserter(Insertion::Enemy{ref mut rot, ..}) => *rot += self.rotation_speed * DELTA,
             Tool::Inserter(Insertion::Decoration{ref mut rot, ..}) => *rot += self.rotation_speed * DELTA,
+            Tool::Inserter(Insertion::Waypoint{ref mut rot, ..}) => *rot += self.rotation_speed * DELTA,
             _ => (),
         }
         Ok(())
@@ -298,9 +311,24 @@ impl GameState for Editor {
                 }
             }
             if self.draw_visibility_cones {
-                graphics::set_color(ctx, BLUE)?;
+                // The colour shows the skill
+                let color = match Skill::PRESETS.iter().position(|p| *p == enemy.skill) {
+                    Some(0) => GREEN,
+                    Some(2) => RED,
+                    _ => BLUE,
+                };
+                graphics::set_color(ctx, color)?;
                 enemy.draw_visibility_cone(ctx, 512.)?;
             }
+            if let Tool::Inserter(Insertion::Waypoint{enemy: e, ..}) = self.current {
+                if e == i {
+                    graphics::set_color(ctx, YELLOW)?;
+                    enemy.draw_patrol(ctx)?;
+                }
+            } else if self.draw_patrol_routes {
+                graphics::set_color(ctx, GREEN)?;
+                enemy.draw_patrol(ctx)?;
+            }
             graphics::set_color(ctx, graphics::WHITE)?;
             enemy.draw(ctx, &s.assets)?;
         }
@@ -421,7 +449,7 @@ impl GameState for Editor {
                 };
                 graphics::draw_ex(ctx, s.assets.get_img(WEAPONS[index as usize].entity_sprite), drawparams)?;
             }
-            Tool::Inserter(Insertion::Enemy{rot}) => {
+            Tool::Inserter(Insertion::Enemy{archetype, rot}) => {
                 let drawparams = graphics::DrawParam {
                     dest,
                     rotation: rot,
@@ -429,7 +457,7 @@ impl GameState for Editor {
                     color: Some(TRANS),
                     .. Default::default()
                 };
-                graphics::draw_ex(ctx, s.assets.get_img(Sprite::Enemy), drawparams)?;
+                graphics::draw_ex(ctx, s.assets.get_img(ARCHETYPES[archetype].spr), drawparams)?;
             }
             Tool::Inserter(Insertion::Decoration{i, rot}) => {
                 let drawparams = graphics::DrawParam {
@@ -441,6 +469,11 @@ impl GameState for Editor {
                 };
                 graphics::draw_ex(ctx, s.assets.get_img(DECORATIONS[i].spr), drawparams)?;
             }
+            Tool::Inserter(Insertion::Waypoint{rot, ..}) => {
+                graphics::set_color(ctx, TRANS)?;
+                graphics::circle(ctx, DrawMode::Fill, dest, 4., 0.5)?;
+                graphics::line(ctx, &[dest, dest + 12. * angle_to_vec(rot)], 1.5)?;
+            }
             Tool::Inserter(Insertion::Exit) => {
                 let drawparams = graphics::DrawParam {
                     dest,
@@ -466,13 +499,14 @@ impl GameState for Editor {
         graphics::set_color(ctx, graphics::WHITE)?;
 
         for (i, mat) in PALETTE.iter().enumerate() {
-            let x = START_X + i as f32 * 36.;
+            let x = START_X + (i % PALETTE_COLUMNS) as f32 * 36.;
+            let y = (i / PALETTE_COLUMNS) as f32 * 32.;
             if Tool::Inserter(Insertion::Material(*mat)) == self.current {
                 graphics::set_color(ctx, YELLOW)?;
-                graphics::rectangle(ctx, DrawMode::Fill, Rect{x: x - 1., y: 15., w: 34., h: 34.})?;
+                graphics::rectangle(ctx, DrawMode::Fill, Rect{x: x - 1., y: y - 1., w: 34., h: 34.})?;
                 graphics::set_color(ctx, graphics::WHITE)?;
             }
-            mat.draw(ctx, &s.assets, x, 16.)?;
+            mat.draw(ctx, &s.assets, x, y)?;
         }
 
         self.entities_bar.draw(ctx, s, if let Tool::Inserter(ins) = self.current{Some(ins)}else{None})?;
@@ -487,8 +521,44 @@ impl GameState for Editor {
         use self::Keycode::*;
         match keycode {
             Z => self.level.save(&self.save).unwrap(),
-            X => self.level = Level::load(&self.save).unwrap(),
+            X => {
+                self.level = Level::load(&self.save).unwrap();
+                self.forget_indices();
+            }
             C => self.draw_visibility_cones.toggle(),
+            V => self.draw_patrol_routes.toggle(),
+            // Cycle the skill of the selected enemies
+            K => if let Tool::Selector(Selection{ref enemies, ..}) = self.current {
+                for &i in enemies {
+                    let skill = &mut self.level.enemies[i].skill;
+                    let next = Skill::PRESETS.iter().position(|p| p == skill).map(|i| i + 1).unwrap_or(0);
+                    *skill = Skill::PRESETS[next % Skill::PRESETS.len()];
+                }
+            }
+            // Edit the patrol route of the selected enemy
+            B => {
+                let selected = match self.current {
+                    Tool::Selector(Selection{ref enemies, ..}) if enemies.len() == 1 => Some(enemies[0]),
+                    _ => None,
+                };
+                if let Some(enemy) = selected {
+                    self.current = Tool::Inserter(Insertion::Waypoint{enemy, rot: self.level.enemies[enemy].pl.obj.rot});
+                }
+            }
+            Backspace if self.editing_patrol().is_some() => {
+                let enemy = self.editing_patrol().unwrap();
+                self.level.enemies[enemy].patrol.pop();
+            }
+            Equals | Minus if self.editing_patrol().is_some() => {
+                let enemy = self.editing_patrol().unwrap();
+                if let Some(waypoint) = self.level.enemies[enemy].patrol.last_mut() {
+                    waypoint.wait = if keycode == Equals {
+                        waypoint.wait + 0.5
+                    } else {
+                        (waypoint.wait - 0.5).max(0.)
+                    };
+                }
+            }
             G => self.snap_on_grid.toggle(),
             P => {
                 s.switch(StateSwitch::Play(self.level.clone()));
@@ -533,8 +603,9 @@ impl GameState for Editor {
                 self.rotation_speed = 0.;
                 if s.modifiers.shift {
                     match self.current {
-                        Tool::Inserter(Insertion::Enemy{ref mut rot}) => *rot -= std::f32::consts::FRAC_PI_4,
+                        Tool::Inserter(Insertion::Enemy{ref mut rot, ..}) => *rot -= std::f32::consts::FRAC_PI_4,
                         Tool::Inserter(Insertion::Decoration{ref mut rot, ..}) => *rot -= std::f32::consts::FRAC_PI_4,
+                        Tool::Inserter(Insertion::Waypoint{ref mut rot, ..}) => *rot -= std::f32::consts::FRAC_PI_4,
                         _ => (),
                     }
                 }
@@ -543,8 +614,9 @@ impl GameState for Editor {
                 self.rotation_speed = 0.;
                 if s.modifiers.shift {
                     match self.current {
-                        Tool::Inserter(Insertion::Enemy{ref mut rot}) => *rot += std::f32::consts::FRAC_PI_4,
+                        Tool::Inserter(Insertion::Enemy{ref mut rot, ..}) => *rot += std::f32::consts::FRAC_PI_4,
                         Tool::Inserter(Insertion::Decoration{ref mut rot, ..}) => *rot += std::f32::consts::FRAC_PI_4,
+                        Tool::Inserter(Insertion::Waypoint{ref mut rot, ..}) => *rot += std::f32::consts::FRAC_PI_4,
                         _ => (),
                     }
                 }
@@ -608,10 +680,13 @@ impl GameState for Editor {
             }
                 
             if s.mouse.y <= 64. {
-                if s.mouse.x > START_X && s.mouse.x < START_X + PALETTE.len() as f32 * 36. {
-                    let i = ((s.mouse.x - START_X) / 36.) as usize;
+                if s.mouse.x > START_X && s.mouse.x < START_X + PALETTE_COLUMNS as f32 * 36. {
+                    let column = ((s.mouse.x - START_X) / 36.) as usize;
+                    let i = (s.mouse.y / 32.) as usize * PALETTE_COLUMNS + column;
 
-                    self.current = Tool::Inserter(Insertion::Material(PALETTE[i]));
+                    if let Some(&mat) = PALETTE.get(i) {
+                        self.current = Tool::Inserter(Insertion::Material(mat));
+                    }
                 }
                 if let Some(ins) = self.entities_bar.click(s.mouse) {
                     self.current = Tool::Inserter(ins);
@@ -691,9 +766,9 @@ impl GameState for Editor {
                         self.level.exit = Some(self.mousepos(&s));
                         self.current = Tool::Selector(Selection{exit: true, .. Default::default()});
                     }
-                    Tool::Inserter(Insertion::Enemy{rot}) => {
+                    Tool::Inserter(Insertion::Enemy{archetype, rot}) => {
                         s.mplayer.play(ctx, Sound::Reload).unwrap();
-                        self.level.enemies.push(Enemy::new(Object::with_rot(mousepos, rot)));
+                        self.level.enemies.push(Enemy::of_type(Object::with_rot(mousepos, rot), archetype));
                         self.level.weapons.push(WEAPONS[0].make_drop(mousepos));
                     },
                     Tool::Inserter(Insertion::Decoration{i, rot}) => {
@@ -705,6 +780,18 @@ impl GameState for Editor {
                     Tool::Inserter(Insertion::Weapon(i)) => {
                         self.level.weapons.push(WEAPONS[i as usize].make_drop(mousepos));
                     },
+                    Tool::Inserter(Insertion::Waypoint{enemy, rot}) => {
+                        if let Some(enemy) = self.level.enemies.get_mut(enemy) {
+                            // Waypoints placed while holding ctrl keep the direction the enemy walks in
+                            enemy.patrol.push(Waypoint {
+                                pos: mousepos,
+                                wait: 1.,
+                                rot: if s.modifiers.ctrl { None } else { Some(rot) },
+                            });
+                        } else {
+                            self.forget_indices();
+                        }
+                    }
                     Tool::Inserter(Insertion::Intel) => self.level.intels.push(mousepos),
                 }
             }}
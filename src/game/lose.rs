@@ -4,7 +4,7 @@ use crate::{
         tex::PosText,
         btn::Button,
     },
-    obj::{health::Health, weapon::WeaponInstance},
+    obj::{health::Health, inventory::Inventory},
 };
 use ggez::{
     Context, GameResult,
@@ -24,7 +24,7 @@ pub struct Lose {
     edit_btn: Option<Button<()>>,
     level: Level,
     health: Health,
-    weapon: Option<WeaponInstance<'static>>
+    inventory: Inventory<'static>,
 }
 
 impl Lose {
@@ -53,14 +53,14 @@ impl Lose {
             edit_btn,
             level: stats.level,
             health: stats.health_left,
-            weapon: stats.weapon,
+            inventory: stats.inventory,
         }))
     }
     fn edit(&self, s: &mut State) {
         s.switch(StateSwitch::Editor(Some(self.level.clone())));
     }
     fn restart(&self, s: &mut State) {
-        s.switch(StateSwitch::PlayWith{lvl: Box::new(self.level.clone()), health: self.health, wep: self.weapon})
+        s.switch(StateSwitch::PlayWith{lvl: Box::new(self.level.clone()), health: self.health, inventory: self.inventory})
     }
 }
 
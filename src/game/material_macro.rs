@@ -1,7 +1,7 @@
 macro_rules! mat {
     (
         MISSING = $missing:ident
-        $($mat:ident = $id:expr, $spr:ident, $solid:expr,)+
+        $($mat:ident = $id:expr, $chr:expr, $spr:ident, $solid:expr,)+
     ) => (
         #[derive(Debug, Copy, Clone, PartialEq, Eq)]
         #[repr(u8)]
@@ -29,6 +29,26 @@ macro_rules! mat {
             }
         }
 
+        impl Material {
+            /// The character representing the material in text level files
+            pub fn to_char(self) -> char {
+                match self {
+                    $(
+                        Material::$mat => $chr,
+                    )*
+                }
+            }
+            /// The material a character in text level files represents, if any
+            pub fn try_from_char(c: char) -> Option<Self> {
+                match c {
+                    $(
+                        $chr => Some(Material::$mat),
+                    )*
+                    _ => None,
+                }
+            }
+        }
+
         impl<'de> Deserialize<'de> for Material {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
                 where D: Deserializer<'de> {
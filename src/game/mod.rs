@@ -3,10 +3,10 @@ use crate::{
     util::{Vector2, Point2},
     ext::{MouseDown, InputState, Modifiers},
     io::{
-        snd::MediaPlayer,
+        snd::{MediaPlayer, Sound},
         tex::Assets,
     },
-    obj::{health::Health, weapon::WeaponInstance},
+    obj::{health::Health, inventory::Inventory, skill::Difficulty},
 };
 use ggez::{
     Context, GameResult,
@@ -18,11 +18,16 @@ use self::world::Level;
 
 /// Stuff related to things in the world
 pub mod world;
+pub mod path;
+pub mod noise;
+pub mod cover;
 pub mod editor;
 pub mod play;
 pub mod menu;
 pub mod lose;
 pub mod win;
+pub mod sim;
+pub mod replay;
 
 use self::menu::Menu;
 use self::world::Statistics;
@@ -34,7 +39,7 @@ pub enum StateSwitch {
     PlayWith{
         lvl: Box<Level>,
         health: Health,
-        wep: Option<WeaponInstance<'static>>,
+        inventory: Inventory<'static>,
     },
     Lose(Box<Statistics>),
     Win(Box<Statistics>),
@@ -63,6 +68,9 @@ pub trait GameState {
     }
     fn mouse_up(&mut self, _: &mut State, _: &mut Context, _: MouseButton) {
 
+    }
+    fn mouse_wheel(&mut self, _: &mut State, _: &mut Context, _: i32) {
+
     }
 }
 
@@ -90,6 +98,9 @@ pub struct State {
     offset: Vector2,
     switch_state: Option<StateSwitch>,
     content: Content,
+    difficulty: Difficulty,
+    /// Where play sessions are recorded to, if they are
+    record: Option<PathBuf>,
 }
 
 const DESIRED_FPS: u32 = 60;
@@ -99,7 +110,7 @@ pub(crate) const DELTA: f32 = 1. / DESIRED_FPS as f32;
 impl Master {
     #[allow(clippy::new_ret_no_self)]
     /// Make a new state object
-    pub fn new(ctx: &mut Context, arg: &str) -> GameResult<Self> {
+    pub fn new(ctx: &mut Context, arg: &str, difficulty: Difficulty, record: Option<PathBuf>) -> GameResult<Self> {
         // Background colour is black
         graphics::set_background_color(ctx, (33, 33, 255, 255).into());
         // Initialise assets
@@ -120,6 +131,8 @@ impl Master {
 
         let mut state = State {
             content,
+            difficulty,
+            record,
             switch_state: None,
             input: Default::default(),
             mouse_down: Default::default(),
@@ -137,6 +150,15 @@ impl Master {
             state,
         })
     }
+    /// Make a new state object that plays back a recorded session
+    pub fn replay(ctx: &mut Context, path: &str) -> GameResult<Self> {
+        let replay = replay::Replay::load(path)?;
+        // The level is kept in the replay, so it isn't tied to any file
+        let mut master = Master::new(ctx, "", replay.difficulty, None)?;
+        master.state.mplayer.stop(ctx, Sound::Music)?;
+        master.gs = play::Play::replay(ctx, &mut master.state, replay)?;
+        Ok(master)
+    }
 }
 
 impl State {
@@ -157,7 +179,7 @@ impl EventHandler for Master {
         if let Some(gsb) = mem::replace(&mut self.state.switch_state, None) {
             use self::StateSwitch::*;
             self.gs = match gsb {
-                PlayWith{lvl, health, wep} => play::Play::new(ctx, &mut self.state, *lvl, Some((health, wep))),
+                PlayWith{lvl, health, inventory} => play::Play::new(ctx, &mut self.state, *lvl, Some((health, inventory))),
                 Play(lvl) => play::Play::new(ctx, &mut self.state, lvl, None),
                 Menu => menu::Menu::new(ctx, &mut self.state),
                 Editor(l) => editor::Editor::new(ctx, &self.state, l),
@@ -262,6 +284,10 @@ impl EventHandler for Master {
         }
         self.gs.mouse_up(&mut self.state, ctx, btn)
     }
+    /// Handle mouse wheel events
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: i32, y: i32) {
+        self.gs.mouse_wheel(&mut self.state, ctx, y)
+    }
     /// Handles mouse movement events
     fn mouse_motion_event(&mut self, _: &mut Context, _: MouseState, x: i32, y: i32, _: i32, _: i32) {
         self.state.mouse = Point2::new(x as f32, y as f32);
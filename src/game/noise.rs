@@ -0,0 +1,89 @@
+//! Noises that alert enemies
+//!
+//! A noise spreads from tile to tile and loses some of its reach with every tile it travels.
+//! Solid tiles muffle it a lot more than open ones, so noises carry around corners
+//! and through doors, but hardly through walls.
+use std::collections::{BinaryHeap, HashSet};
+use std::cmp::Reverse;
+
+use crate::util::Point2;
+use super::world::Grid;
+
+/// How much more reach a noise loses going through a solid tile than an open one
+const WALL_DAMPING: u32 = 5;
+
+/// Reach lost moving to a neighbouring tile
+const STRAIGHT: u32 = 32;
+/// Reach lost moving to a diagonally neighbouring tile
+const DIAGONAL: u32 = 45;
+
+/// Radius of the noise a bullet makes hitting a wall
+pub const IMPACT_NOISE: f32 = 160.;
+/// Radius of the noise of a wall or decoration breaking
+pub const BREAK_NOISE: f32 = 320.;
+/// Radius of the noise of an explosion
+pub const EXPLOSION_NOISE: f32 = 960.;
+/// Radius of the noise of a melee strike that doesn't take its target down silently
+pub const MELEE_NOISE: f32 = 128.;
+/// Radius of the noise the player makes every tick while sprinting
+pub const SPRINT_NOISE: f32 = 96.;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Noise {
+    pub pos: Point2,
+    /// How far the noise can be heard without anything in the way
+    pub radius: f32,
+}
+
+impl Noise {
+    #[inline]
+    pub fn new(pos: Point2, radius: f32) -> Self {
+        Noise {
+            pos,
+            radius,
+        }
+    }
+    /// All the tiles in which the noise can be heard
+    pub fn audible_tiles(&self, grid: &Grid) -> HashSet<(u16, u16)> {
+        let mut heard = HashSet::new();
+        let start = Grid::snap(self.pos);
+        if grid.get(start.0, start.1).is_none() {
+            return heard;
+        }
+        let reach = self.radius.max(0.) as u32;
+        let width = i32::from(grid.width());
+        let height = i32::from(grid.height());
+
+        let mut open = BinaryHeap::new();
+        open.push(Reverse((0, start)));
+
+        while let Some(Reverse((cost, tile))) = open.pop() {
+            if !heard.insert(tile) {
+                continue
+            }
+            for dx in -1i32..=1 {
+                for dy in -1i32..=1 {
+                    if dx == 0 && dy == 0 {
+                        continue
+                    }
+                    let x = i32::from(tile.0) + dx;
+                    let y = i32::from(tile.1) + dy;
+                    if x < 0 || y < 0 || x >= width || y >= height {
+                        continue
+                    }
+                    let next = (x as u16, y as u16);
+                    let mut step = if dx != 0 && dy != 0 {DIAGONAL} else {STRAIGHT};
+                    if grid.is_solid_tuple(next) {
+                        step *= WALL_DAMPING;
+                    }
+
+                    let new_cost = cost + step;
+                    if new_cost <= reach && !heard.contains(&next) {
+                        open.push(Reverse((new_cost, next)));
+                    }
+                }
+            }
+        }
+        heard
+    }
+}
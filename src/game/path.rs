@@ -0,0 +1,279 @@
+//! Path finding over the tiles of a `Grid`
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::cmp::{Ordering, Reverse};
+
+use crate::{
+    util::{Point2, Vector2},
+    obj::decoration::DecorationObj,
+};
+use super::world::Grid;
+
+/// How far paths keep away from walls and solid decorations
+const CLEARANCE: f32 = 14.;
+
+/// Cost of moving to a neighbouring tile
+const STRAIGHT: u32 = 10;
+/// Cost of moving to a diagonally neighbouring tile
+const DIAGONAL: u32 = 14;
+
+#[derive(Debug, Clone)]
+/// A route to a position that is walked waypoint by waypoint
+pub struct Path {
+    goal: Point2,
+    /// The remaining waypoints, the next one last
+    waypoints: Vec<Point2>,
+}
+
+impl Path {
+    /// Finds a path from one position to another going around solid tiles and decorations
+    ///
+    /// Returns `None` if the destination can't be reached.
+    pub fn find(grid: &Grid, decorations: &[DecorationObj], walkable: &WalkMap, from: Point2, to: Point2) -> Option<Self> {
+        if is_clear(grid, decorations, from, to) {
+            return Some(Path {
+                goal: to,
+                waypoints: vec![to],
+            });
+        }
+
+        let tiles = a_star(walkable, Grid::snap(from), Grid::snap(to))?;
+        let mut corners: Vec<Point2> = tiles.into_iter().skip(1).map(tile_center).collect();
+        // Walk to the actual destination instead of the center of its tile
+        if let Some(last) = corners.last_mut() {
+            *last = to;
+        } else {
+            corners.push(to);
+        }
+
+        // Skip waypoints that can be walked to in a straight line
+        let mut waypoints = Vec::new();
+        let mut cur = from;
+        let mut i = 0;
+        while i < corners.len() {
+            let furthest = corners[i+1..].iter()
+                .rposition(|&corner| is_clear(grid, decorations, cur, corner))
+                .map(|j| i + 1 + j)
+                .unwrap_or(i);
+            cur = corners[furthest];
+            waypoints.push(cur);
+            i = furthest + 1;
+        }
+        waypoints.reverse();
+
+        Some(Path {
+            goal: to,
+            waypoints,
+        })
+    }
+    #[inline]
+    /// The destination of the path
+    pub fn goal(&self) -> Point2 {
+        self.goal
+    }
+    /// Moves the destination without finding a new path
+    ///
+    /// Only meant for small moves, like within the same tile.
+    pub fn retarget(&mut self, goal: Point2) {
+        if let Some(last) = self.waypoints.first_mut() {
+            *last = goal;
+        }
+        self.goal = goal;
+    }
+    #[inline]
+    /// The waypoint currently walked towards
+    pub fn next(&self) -> Option<Point2> {
+        self.waypoints.last().cloned()
+    }
+    #[inline]
+    /// Moves on to the next waypoint
+    pub fn advance(&mut self) {
+        self.waypoints.pop();
+    }
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.waypoints.is_empty()
+    }
+}
+
+#[inline]
+pub fn tile_center((x, y): (u16, u16)) -> Point2 {
+    Point2::new(f32::from(x) * 32. + 16., f32::from(y) * 32. + 16.)
+}
+
+#[derive(Debug, Clone)]
+/// Which tiles can be walked on
+///
+/// Worked out once for the whole grid and kept until a tile or decoration breaks,
+/// so finding paths doesn't have to check every decoration for every tile.
+pub struct WalkMap {
+    width: u16,
+    height: u16,
+    tiles: Vec<bool>,
+}
+
+impl WalkMap {
+    pub fn new(grid: &Grid, decorations: &[DecorationObj]) -> Self {
+        let width = grid.width();
+        let height = grid.height();
+        let mut tiles: Vec<bool> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| !grid.is_solid(x, y))
+            .collect();
+        for decoration in decorations.iter().filter(|d| d.is_solid()) {
+            let (x, y) = Grid::snap(decoration.obj.pos);
+            if x < width && y < height {
+                tiles[x as usize + y as usize * width as usize] = false;
+            }
+        }
+        WalkMap {
+            width,
+            height,
+            tiles,
+        }
+    }
+    #[inline]
+    fn idx(&self, (x, y): (u16, u16)) -> usize {
+        x as usize + y as usize * self.width as usize
+    }
+    /// Whether a tile can be walked on
+    pub fn is_walkable(&self, tile: (u16, u16)) -> bool {
+        tile.0 < self.width && tile.1 < self.height && self.tiles[self.idx(tile)]
+    }
+    /// The walkable tiles next to a tile and the cost of stepping to them
+    ///
+    /// Diagonal steps that would squeeze past a corner are left out.
+    fn neighbours(&self, (tx, ty): (u16, u16)) -> impl Iterator<Item=((u16, u16), u32)> + '_ {
+        (-1i32..=1).flat_map(|dx| (-1i32..=1).map(move |dy| (dx, dy)))
+            .filter_map(move |(dx, dy)| {
+                if dx == 0 && dy == 0 {
+                    return None
+                }
+                let x = i32::from(tx) + dx;
+                let y = i32::from(ty) + dy;
+                if x < 0 || y < 0 {
+                    return None
+                }
+                let next = (x as u16, y as u16);
+                if !self.is_walkable(next) {
+                    return None
+                }
+                if dx != 0 && dy != 0 {
+                    if !self.is_walkable((next.0, ty)) || !self.is_walkable((tx, next.1)) {
+                        return None
+                    }
+                    Some((next, DIAGONAL))
+                } else {
+                    Some((next, STRAIGHT))
+                }
+            })
+    }
+    /// The tiles that can be walked to from a tile without going more than `radius` tiles away from it
+    ///
+    /// The tile itself comes first, then the others from the closest to the furthest.
+    pub fn reachable(&self, start: (u16, u16), radius: u16) -> Vec<(u16, u16)> {
+        let mut reached = HashSet::new();
+        let mut order = Vec::new();
+        if start.0 >= self.width || start.1 >= self.height {
+            return order;
+        }
+        let within = |(x, y): (u16, u16)| {
+            let dx = if x > start.0 {x - start.0} else {start.0 - x};
+            let dy = if y > start.1 {y - start.1} else {start.1 - y};
+            dx <= radius && dy <= radius
+        };
+        let mut queue = VecDeque::new();
+        reached.insert(start);
+        queue.push_back(start);
+        while let Some(tile) = queue.pop_front() {
+            order.push(tile);
+            for (next, _) in self.neighbours(tile) {
+                if within(next) && reached.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        order
+    }
+}
+
+/// Whether a body can walk in a straight line between two points
+///
+/// Casts a ray along the middle and both sides of the body, so corners aren't cut.
+fn is_clear(grid: &Grid, decorations: &[DecorationObj], from: Point2, to: Point2) -> bool {
+    let dist = to - from;
+    let len = dist.norm();
+    if len <= std::f32::EPSILON {
+        return true;
+    }
+    let side = CLEARANCE / len * Vector2::new(-dist.y, dist.x);
+
+    [Vector2::new(0., 0.), side, -side].iter()
+        .all(|&off| grid.ray_cast(from + off, dist, true).full())
+    && decorations.iter()
+        .filter(|d| d.is_solid())
+        .all(|d| Grid::dist_line_circle(from, dist, d.obj.pos) > CLEARANCE + d.shape().bounding_radius())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Node {
+    cost: u32,
+    tile: (u16, u16),
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cost.cmp(&other.cost).then_with(|| self.tile.cmp(&other.tile))
+    }
+}
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Octile distance between two tiles
+fn heuristic((ax, ay): (u16, u16), (bx, by): (u16, u16)) -> u32 {
+    let dx = u32::from(if ax > bx {ax - bx} else {bx - ax});
+    let dy = u32::from(if ay > by {ay - by} else {by - ay});
+    let (min, max) = if dx < dy {(dx, dy)} else {(dy, dx)};
+    DIAGONAL * min + STRAIGHT * (max - min)
+}
+
+/// Finds the cheapest list of tiles from `start` to `goal`, both included
+fn a_star(walkable: &WalkMap, start: (u16, u16), goal: (u16, u16)) -> Option<Vec<(u16, u16)>> {
+    if start.0 >= walkable.width || start.1 >= walkable.height || !walkable.is_walkable(goal) {
+        return None;
+    }
+
+    let len = walkable.tiles.len();
+    let mut costs = vec![std::u32::MAX; len];
+    let mut came_from = vec![None; len];
+    let mut open = BinaryHeap::new();
+
+    costs[walkable.idx(start)] = 0;
+    open.push(Reverse(Node{cost: heuristic(start, goal), tile: start}));
+
+    while let Some(Reverse(Node{tile, ..})) = open.pop() {
+        if tile == goal {
+            let mut tiles = vec![goal];
+            let mut cur = goal;
+            while let Some(prev) = came_from[walkable.idx(cur)] {
+                tiles.push(prev);
+                cur = prev;
+            }
+            tiles.reverse();
+            return Some(tiles);
+        }
+        let cost = costs[walkable.idx(tile)];
+
+        for (next, step) in walkable.neighbours(tile) {
+            let new_cost = cost + step;
+            if new_cost < costs[walkable.idx(next)] {
+                costs[walkable.idx(next)] = new_cost;
+                came_from[walkable.idx(next)] = Some(tile);
+                open.push(Reverse(Node{cost: new_cost + heuristic(next, goal), tile: next}));
+            }
+        }
+    }
+    None
+}
@@ -3,25 +3,29 @@ use crate::{
     util::{
         BLUE, GREEN, RED,
         angle_to_vec, angle_from_vec,
-        Vector2, Point2
+        Point2
     },
-    io::{
-        tex::{Assets, Sprite, PosText},
-        snd::Sound,
-    },
-    obj::{Object, pickup::Pickup, player::Player, enemy::{Enemy, Chaser}, health::Health, weapon::WeaponInstance},
+    io::tex::{Assets, Sprite, PosText},
+    obj::{Object, health::Health, inventory::{Inventory, Slot}},
 };
 use ggez::{
     Context, GameResult,
     graphics::{
-        self, Drawable, DrawMode, WHITE, Rect,
+        self, Drawable, DrawMode, Color, WHITE, Rect,
         spritebatch::SpriteBatch,
     },
     event::{Keycode, MouseButton}
 };
 
-use rand::{thread_rng, prelude::SliceRandom};
-use super::{DELTA, State, GameState, StateSwitch, world::{Level, Statistics, World}};
+use rand::{Rng, thread_rng, prelude::SliceRandom};
+use super::{
+    State, GameState, StateSwitch, DELTA,
+    world::Level,
+    sim::{Simulation, Input, Event},
+    replay::{Replay, Loadout, Frame, InputEvent, unused_path},
+};
+
+use std::path::PathBuf;
 
 #[derive(Debug, Copy, Clone)]
 enum Blood {
@@ -36,7 +40,7 @@ struct BloodSplatter {
 }
 
 impl BloodSplatter {
-    fn new(mut o: Object) -> Self {
+    fn new<R: Rng>(mut o: Object, rng: &mut R) -> Self {
         o.pos += 16. * angle_to_vec(o.rot);
         BloodSplatter {
             o,
@@ -46,7 +50,7 @@ impl BloodSplatter {
                 Blood::B2,
                 Blood::B3,
                 Blood::B3,
-            ].choose(&mut thread_rng()).unwrap(),
+            ].choose(rng).unwrap(),
         }
     }
     fn draw(&self, ctx: &mut Context, a: &Assets) -> GameResult<()> {
@@ -64,260 +68,194 @@ pub struct Play {
     arm_text: PosText,
     reload_text: PosText,
     wep_text: PosText,
+    /// The weapons carried in each slot
+    inv_text: PosText,
     status_text: PosText,
-    world: World,
+    sim: Simulation,
+    /// Key and mouse events waiting for the next tick
+    pending: Vec<InputEvent>,
+    /// The session being recorded and the path it was asked to be saved at
+    recording: Option<(Replay, PathBuf)>,
+    /// The session being played back and the index of its next frame
+    playback: Option<(Replay, usize)>,
     holes: SpriteBatch,
     bloods: Vec<BloodSplatter>,
-    cur_pickup: Option<usize>,
-    victory_time: f32,
-    misses: usize,
-    initial: (Health, Option<WeaponInstance<'static>>),
-    level: Level,
+    /// Explosions being shown, with their blast radius and how long they're still shown for
+    explosions: Vec<(Point2, f32, f32)>,
 }
 
+/// How long an explosion is shown for
+const EXPLOSION_TIME: f32 = 0.4;
+
 impl Play {
     #[allow(clippy::new_ret_no_self)]
-    pub fn new(ctx: &mut Context, s: &mut State, level: Level, pl: Option<(Health, Option<WeaponInstance<'static>>)>) -> GameResult<Box<dyn GameState>> {
-        let mut player = Player::from_point(level.start_point.unwrap_or_else(|| Point2::new(500., 500.)));
-        if let Some((h, w)) = pl {
-            player = player.with_health(h).with_weapon(w);
+    pub fn new(ctx: &mut Context, s: &mut State, level: Level, pl: Option<(Health, Inventory<'static>)>) -> GameResult<Box<dyn GameState>> {
+        let seed = thread_rng().gen();
+        // Sessions are only recorded when asked for
+        let recording = match &s.record {
+            Some(path) => {
+                let loadout = pl.as_ref().map(|(health, inventory)| Loadout::new(*health, inventory));
+                Some((Replay::new(&level, loadout, seed, s.difficulty)?, path.clone()))
+            }
+            None => None,
         };
-
+        Play::with_sim(ctx, s, Simulation::new(level, pl, seed, s.difficulty), recording, None)
+    }
+    /// Play back a recorded session
+    pub fn replay(ctx: &mut Context, s: &mut State, replay: Replay) -> GameResult<Box<dyn GameState>> {
+        let level = replay.played_level()?;
+        let pl = match &replay.loadout {
+            Some(loadout) => Some(loadout.restore()?),
+            None => None,
+        };
+        let sim = Simulation::new(level, pl, replay.seed, replay.difficulty);
+        Play::with_sim(ctx, s, sim, None, Some((replay, 0)))
+    }
+    fn with_sim(ctx: &mut Context, s: &mut State, sim: Simulation, recording: Option<(Replay, PathBuf)>, playback: Option<(Replay, usize)>) -> GameResult<Box<dyn GameState>> {
         Ok(Box::new(
             Play {
-                level: level.clone(),
-                initial: (player.health, player.wep),
                 hp_text: s.assets.text(ctx, Point2::new(4., 4.), "100")?,
                 arm_text: s.assets.text(ctx, Point2::new(4., 33.), "100")?,
                 reload_text: s.assets.text(ctx, Point2::new(4., 62.), "0.0s")?,
                 wep_text: s.assets.text(ctx, Point2::new(2., 87.), "BFG 0/0")?,
+                inv_text: s.assets.text(ctx, Point2::new(2., 103.), "")?,
                 status_text: s.assets.text(ctx, Point2::new(s.width as f32 / 2., s.height as f32 / 2.+32.), "")?,
-                misses: 0,
-                victory_time: 0.,
                 bloods: Vec::new(),
-                cur_pickup: None,
-                world: {
-                    let mut world = World {
-                        enemies: level.enemies,
-                        bullets: Vec::new(),
-                        weapons: level.weapons,
-                        player,
-                        grid: level.grid,
-                        exit: level.exit,
-                        intels: level.intels,
-                        decorations: level.decorations,
-                        pickups: level.pickups.into_iter().map(|(p, i)| Pickup::new(p, i)).collect(),
-                    };
-                    world.enemy_pickup();
-                    world.player_pickup();
-
-                    if world.player.wep.is_none() {
-                        eprintln!("Warning: player has no weapon");
-                    }
-
-                    for enemy_pos in world.enemies.iter().filter_map(|enemy| if enemy.pl.wep.is_none() {Some(enemy.pl.obj.pos)}else{None}) {
-                        eprintln!("Warning: enemy at {:.2} has no weapon", enemy_pos)
-                    }
-
-                    world
-                },
+                explosions: Vec::new(),
+                sim,
+                pending: Vec::new(),
+                recording,
+                playback,
                 holes: SpriteBatch::new(s.assets.get_img(Sprite::Hole).clone()),
             }
         ))
     }
+    fn record(&mut self, event: InputEvent) {
+        // Live input is ignored during play back
+        if self.playback.is_none() {
+            self.pending.push(event);
+        }
+    }
+}
+
+impl Drop for Play {
+    fn drop(&mut self) {
+        if let Some((recording, path)) = &self.recording {
+            if let Err(e) = recording.save(unused_path(path)) {
+                eprintln!("Couldn't save replay: {}", e);
+            }
+        }
+    }
 }
 
 impl GameState for Play {
-    #[allow(clippy::cyclomatic_complexity)]
     fn update(&mut self, s: &mut State, ctx: &mut Context) -> GameResult<()> {
-        self.hp_text.update_text(&s.assets, ctx, &format!("{:02.0}", self.world.player.health.hp))?;
-        self.arm_text.update_text(&s.assets, ctx, &format!("{:02.0}", self.world.player.health.armour))?;
-        if let Some(wep) = self.world.player.wep {
+        let world = &self.sim.world;
+        self.hp_text.update_text(&s.assets, ctx, &format!("{:02.0}", world.player.health.hp))?;
+        self.arm_text.update_text(&s.assets, ctx, &format!("{:02.0}", world.player.health.armour))?;
+        if let Some(wep) = world.player.wep {
             self.reload_text.update_text(&s.assets, ctx, &format!("{:.1}s", wep.loading_time))?;
             self.wep_text.update_text(&s.assets, ctx, &format!("{} ({:.3} {:.1}s)", wep, wep.jerk, wep.jerk_decay))?;
         }
-        if let Some(i) = self.cur_pickup {
-            self.status_text.update_text(&s.assets, ctx, &format!("Press F to pick up {}", self.world.weapons[i]))?;
+        let inventory = world.player.full_inventory();
+        let slots: Vec<_> = Slot::ALL.iter()
+            .enumerate()
+            .map(|(i, &slot)| match inventory.get(slot) {
+                Some(wep) => format!("{} {} ({})", i + 1, wep.weapon.name, world.player.rounds(wep.weapon.calibre)),
+                None => format!("{} -", i + 1),
+            })
+            .collect();
+        self.inv_text.update_text(&s.assets, ctx, &slots.join(" | "))?;
+        if let Some(i) = self.sim.cur_pickup {
+            self.status_text.update_text(&s.assets, ctx, &format!("Press F to pick up {}", world.weapons[i]))?;
         } else {
             self.status_text.update_text(&s.assets, ctx, "")?;
         }
 
-        let mut deads = Vec::new();
-        for (i, bullet) in self.world.bullets.iter_mut().enumerate().rev() {
-            let hit = bullet.update(&self.world.grid, &mut self.world.player, &mut *self.world.enemies);
-            
-            use crate::obj::bullet::Hit;
-
-            match hit {
-                Hit::None => (),
-                Hit::Wall => {
-                    s.mplayer.play(ctx, bullet.weapon.impact_snd)?;
-                    let dir = angle_to_vec(bullet.obj.rot);
-                    bullet.obj.pos += Vector2::new(5.*dir.x.signum(), 5.*dir.y.signum());
-                    self.holes.add(bullet.obj.drawparams());
-                    self.misses += 1;
-                    deads.push(i);
-                }
-                Hit::Player => {
-                    deads.push(i);
-                    self.bloods.push(BloodSplatter::new(bullet.obj.clone()));
-                    s.mplayer.play(ctx, Sound::Hit)?;
-
-                    if self.world.player.health.is_dead() {
-                        s.switch(StateSwitch::Lose(Box::new(Statistics{
-                            hits: self.bloods.len(),
-                            misses: self.misses,
-                            enemies_left: self.world.enemies.len(),
-                            health_left: self.initial.0,
-                            level: self.level.clone(),
-                            weapon: self.initial.1,
-                        })));
-                        s.mplayer.play(ctx, Sound::Death)?;
-                    } else {
-                        s.mplayer.play(ctx, Sound::Hurt)?;
-                    }
-                }
-                Hit::Enemy(e) => {
-                    deads.push(i);
-                    let enemy = &self.world.enemies[e];
-                    s.mplayer.play(ctx, Sound::Hit)?;
-
-                    self.bloods.push(BloodSplatter::new(bullet.obj.clone()));
-                    if enemy.pl.health.is_dead() {
-                        s.mplayer.play(ctx, Sound::Death)?;
-
-                        let Enemy{pl: Player{wep, obj: Object{pos, ..}, ..}, ..}
-                            = self.world.enemies.remove(e);
-                        if let Some(wep) = wep {
-                            self.world.weapons.push(wep.into_drop(pos));
-                        }
-                    } else {
-                        if !enemy.behaviour.chasing() {
-                            self.world.enemies[e].behaviour = Chaser::LookAround{
-                                dir: bullet.obj.pos - enemy.pl.obj.pos
-                            };
-                        }
-                        s.mplayer.play(ctx, Sound::Hurt)?;
-                    }
-                }
+        let frame = if let Some((replay, i)) = &mut self.playback {
+            if let Some(frame) = replay.frames.get(*i) {
+                *i += 1;
+                frame.clone()
+            } else {
+                // The replay has ended
+                return Ok(())
             }
-        }
-        for i in deads {
-            self.world.bullets.remove(i);
-        }
+        } else {
+            let frame = Frame {
+                input: s.input.clone(),
+                modifiers: s.modifiers.clone(),
+                mouse_down: s.mouse_down.clone(),
+                mouse: s.mouse - s.offset,
+                events: std::mem::replace(&mut self.pending, Vec::new()),
+            };
+            if let Some((recording, _)) = &mut self.recording {
+                recording.frames.push(frame.clone());
+            }
+            frame
+        };
 
-        let mut deads = Vec::new();
-        for (i, &intel) in self.world.intels.iter().enumerate().rev() {
-            if (intel-self.world.player.obj.pos).norm() <= 15. {
-                deads.push(i);
-                s.mplayer.play(ctx, Sound::Hit)?;
+        let mut input = Input {
+            hor: frame.input.hor(),
+            ver: frame.input.ver(),
+            sprint: frame.modifiers.shift,
+            trigger: frame.mouse_down.left,
+            rot: angle_from_vec(frame.mouse - world.player.obj.pos),
+            .. Input::default()
+        };
+        for &event in &frame.events {
+            if let Some(MouseButton::Left) = event.released_button() {
+                input.shoot = true;
             }
-        }
-        for i in deads {
-            self.world.intels.remove(i);
-        }
-        let mut deads = Vec::new();
-        for (i, pickup) in self.world.pickups.iter().enumerate().rev() {
-            if (pickup.pos-self.world.player.obj.pos).norm() <= 15. {
-                pickup.apply(&mut self.world.player.health);
-                deads.push(i);
-                s.mplayer.play(ctx, Sound::Hit)?;
+            // Scrolling down goes to the next weapon
+            if let Some(y) = event.scrolled() {
+                input.cycle -= y;
             }
-        }
-        for i in deads {
-            self.world.pickups.remove(i);
-        }
-        self.cur_pickup = None;
-        for (i, weapon) in self.world.weapons.iter().enumerate().rev() {
-            if (weapon.pos-self.world.player.obj.pos).norm() <= 29. {
-                self.cur_pickup = Some(i);
-                break
+            match event.released_key() {
+                Some(Keycode::R) => input.reload = true,
+                Some(Keycode::V) => input.melee = true,
+                Some(Keycode::Num1) => input.switch = Some(Slot::Primary),
+                Some(Keycode::Num2) => input.switch = Some(Slot::Secondary),
+                Some(Keycode::Num3) => input.switch = Some(Slot::Sidearm),
+                Some(Keycode::Num4) => input.switch = Some(Slot::Throwable),
+                Some(Keycode::F) => input.pick_up = true,
+                _ => (),
             }
         }
 
-        // Define player velocity here already because enemies need it
-        let player_vel = Vector2::new(s.input.hor(), s.input.ver());
-
-        for enemy in self.world.enemies.iter_mut() {
-            if enemy.can_see(self.world.player.obj.pos, &self.world.grid) {
-                enemy.behaviour = Chaser::LastKnown{
-                    pos: self.world.player.obj.pos,
-                    vel: player_vel,
-                };
-
-                if let Some(wep) = &mut enemy.pl.wep {
-                    if let Some(bm) = wep.shoot(ctx, &mut s.mplayer)? {
-                        let pos = enemy.pl.obj.pos + 20. * angle_to_vec(enemy.pl.obj.rot);
-                        let mut bul = Object::new(pos);
-                        bul.rot = enemy.pl.obj.rot;
-
-                        self.world.bullets.push(bm.make(bul));
-                    }
-                }
-            }
-            enemy.update(ctx, &mut s.mplayer)?;
+        for explosion in &mut self.explosions {
+            explosion.2 -= DELTA;
         }
+        self.explosions.retain(|e| e.2 > 0.);
 
-        let speed = if s.modifiers.shift {
-            200.
-        } else {
-            100.
-        };
-        if let Some(wep) = &mut self.world.player.wep {
-            wep.update(ctx, &mut s.mplayer)?;
-            if wep.cur_clip > 0 && s.mouse_down.left && wep.weapon.fire_mode.is_auto() {
-                if let Some(bm) = wep.shoot(ctx, &mut s.mplayer)? {
-                    let pos = self.world.player.obj.pos + 20. * angle_to_vec(self.world.player.obj.rot);
-                    let mut bul = Object::new(pos);
-                    bul.rot = self.world.player.obj.rot;
-
-                    self.world.bullets.push(bm.make(bul));
+        for event in self.sim.tick(&input) {
+            match event {
+                Event::Sound(snd) => s.mplayer.play(ctx, snd)?,
+                Event::Impact(obj) => {
+                    self.holes.add(obj.drawparams());
                 }
+                Event::Explosion(pos, radius) => self.explosions.push((pos, radius, EXPLOSION_TIME)),
+                Event::PlayerHit(obj) | Event::EnemyHit(obj) => self.bloods.push(BloodSplatter::new(obj, &mut self.sim.rng)),
+                Event::Lose => s.switch(StateSwitch::Lose(Box::new(self.sim.initial_statistics()))),
+                Event::Win => s.switch(StateSwitch::Win(Box::new(self.sim.statistics()))),
+                Event::Shot(_) | Event::EnemyDeath(_) | Event::Noise(_) | Event::Broken(_) => (),
             }
         }
-        self.world.player.obj.move_on_grid(player_vel, speed, &self.world.grid);
-
-        let game_won = match self.world.exit {
-            Some(p) => self.world.intels.is_empty() && (p - self.world.player.obj.pos).norm() < 32.,
-            None => self.world.enemies.is_empty(),
-        };
-
-        if game_won && self.victory_time <= 0. {
-            s.mplayer.play(ctx, Sound::Victory)?;
-            self.victory_time += DELTA;
-        } else if self.victory_time > 0. {
-            self.victory_time += DELTA;
-        }
-        if self.victory_time >= 2. {
-            s.switch(StateSwitch::Win(Box::new(Statistics{
-                level: self.level.clone(),
-                hits: self.bloods.len(),
-                misses: self.misses,
-                enemies_left: self.world.enemies.len(),
-                health_left: self.world.player.health,
-                weapon: self.world.player.wep,
-            })));
-        }
         Ok(())
     }
     fn logic(&mut self, s: &mut State, _ctx: &mut Context) -> GameResult<()> {
-        let dist = s.mouse - s.offset - self.world.player.obj.pos;
-
-        self.world.player.obj.rot = angle_from_vec(dist);
-
         // Center the camera on the player
-        let p = self.world.player.obj.pos;
+        let p = self.sim.world.player.obj.pos;
         s.focus_on(p);
         Ok(())
     }
 
     fn draw(&mut self, s: &State, ctx: &mut Context) -> GameResult<()> {
         graphics::set_color(ctx, WHITE)?;
-        self.world.grid.draw(ctx, &s.assets)?;
+        self.sim.world.grid.draw(ctx, &s.assets)?;
 
         self.holes.draw_ex(ctx, Default::default())?;
 
-        for &intel in &self.world.intels {
+        for &intel in &self.sim.world.intels {
             let drawparams = graphics::DrawParam {
                 dest: intel,
                 offset: Point2::new(0.5, 0.5),
@@ -326,7 +264,7 @@ impl GameState for Play {
             };
             graphics::draw_ex(ctx, s.assets.get_img(Sprite::Intel), drawparams)?;
         }
-        for decoration in &self.world.decorations {
+        for decoration in &self.sim.world.decorations {
             decoration.draw(ctx, &s.assets)?;
         }
 
@@ -334,7 +272,7 @@ impl GameState for Play {
             blood.draw(ctx, &s.assets)?;
         }
 
-        for pickup in &self.world.pickups {
+        for pickup in &self.sim.world.pickups {
             let drawparams = graphics::DrawParam {
                 dest: pickup.pos,
                 offset: Point2::new(0.5, 0.5),
@@ -343,7 +281,7 @@ impl GameState for Play {
             };
             graphics::draw_ex(ctx, s.assets.get_img(pickup.pickup_type.spr), drawparams)?;
         }
-        for wep in &self.world.weapons {
+        for wep in &self.sim.world.weapons {
             let drawparams = graphics::DrawParam {
                 dest: wep.pos,
                 offset: Point2::new(0.5, 0.5),
@@ -353,14 +291,23 @@ impl GameState for Play {
             graphics::draw_ex(ctx, s.assets.get_img(wep.weapon.entity_sprite), drawparams)?;
         }
 
-        self.world.player.draw_player(ctx, &s.assets)?;
+        self.sim.world.player.draw_player(ctx, &s.assets)?;
 
-        for enemy in &self.world.enemies {
+        for enemy in &self.sim.world.enemies {
             enemy.draw(ctx, &s.assets)?;
         }
-        for bullet in &self.world.bullets {
+        for bullet in &self.sim.world.bullets {
             bullet.draw(ctx, &s.assets)?;
         }
+        for grenade in &self.sim.world.grenades {
+            grenade.draw(ctx, &s.assets)?;
+        }
+        for &(pos, radius, time) in &self.explosions {
+            let t = time / EXPLOSION_TIME;
+            graphics::set_color(ctx, Color::new(1., 0.6, 0.1, 0.7 * t))?;
+            graphics::circle(ctx, DrawMode::Fill, pos, radius * (1. - 0.5 * t), 1.)?;
+        }
+        graphics::set_color(ctx, WHITE)?;
 
         Ok(())
     }
@@ -370,16 +317,17 @@ impl GameState for Play {
         graphics::rectangle(ctx, DrawMode::Fill, Rect{x: 1., y: 29., w: 102., h: 26.})?;
         graphics::rectangle(ctx, DrawMode::Fill, Rect{x: 1., y: 57., w: 102., h: 26.})?;
         graphics::set_color(ctx, GREEN)?;
-        graphics::rectangle(ctx, DrawMode::Fill, Rect{x: 2., y: 2., w: self.world.player.health.hp.limit(0., 100.), h: 24.})?;
+        graphics::rectangle(ctx, DrawMode::Fill, Rect{x: 2., y: 2., w: self.sim.world.player.health.hp.limit(0., 100.), h: 24.})?;
         graphics::set_color(ctx, BLUE)?;
-        graphics::rectangle(ctx, DrawMode::Fill, Rect{x: 2., y: 30., w: self.world.player.health.armour.limit(0., 100.), h: 24.})?;
+        graphics::rectangle(ctx, DrawMode::Fill, Rect{x: 2., y: 30., w: self.sim.world.player.health.armour.limit(0., 100.), h: 24.})?;
         graphics::set_color(ctx, RED)?;
-        graphics::rectangle(ctx, DrawMode::Fill, Rect{x: 2., y: 58., w: self.world.player.wep.map(|m| m.loading_time).unwrap_or(0.).limit(0., 1.)*100., h: 24.})?;
+        graphics::rectangle(ctx, DrawMode::Fill, Rect{x: 2., y: 58., w: self.sim.world.player.wep.map(|m| m.loading_time).unwrap_or(0.).limit(0., 1.)*100., h: 24.})?;
         graphics::set_color(ctx, WHITE)?;
         self.hp_text.draw_text(ctx)?;
         self.arm_text.draw_text(ctx)?;
         self.reload_text.draw_text(ctx)?;
         self.wep_text.draw_text(ctx)?;
+        self.inv_text.draw_text(ctx)?;
         self.status_text.draw_center(ctx)?;
 
         graphics::set_color(ctx, RED)?;
@@ -390,43 +338,19 @@ impl GameState for Play {
         };
         graphics::draw_ex(ctx, s.assets.get_img(Sprite::Crosshair), drawparams)
     }
-    fn mouse_up(&mut self, s: &mut State, ctx: &mut Context, btn: MouseButton) {
-        if let MouseButton::Left = btn {
-            if let Some(wep) = &mut self.world.player.wep {
-                if let Some(bm) = wep.shoot(ctx, &mut s.mplayer).unwrap() {
-                    let pos = self.world.player.obj.pos + 20. * angle_to_vec(self.world.player.obj.rot);
-                    let mut bul = Object::new(pos);
-                    bul.rot = self.world.player.obj.rot;
-
-                    self.world.bullets.push(bm.make(bul));
-                }
-            }
-        }
+    fn key_down(&mut self, _s: &mut State, _ctx: &mut Context, keycode: Keycode) {
+        self.record(InputEvent::key_down(keycode));
     }
-    fn key_up(&mut self, s: &mut State, ctx: &mut Context, keycode: Keycode) {
-        use self::Keycode::*;
-        match keycode {
-            R => {
-                if let Some(wep) = &mut self.world.player.wep {
-                    wep.reload(ctx, &mut s.mplayer).unwrap()
-                } else {
-                    self.world.bullets.push(crate::obj::bullet::Bullet{obj: self.world.player.obj.clone(), weapon: &crate::obj::weapon::WEAPONS[0]});
-                }
-            },
-            F => {
-                if let Some(i) = self.cur_pickup {
-                    self.world.player.wep = Some(WeaponInstance::from_drop(
-                        if let Some(wep) = self.world.player.wep {
-                            let w = wep.into_drop(self.world.player.obj.pos);
-                            std::mem::replace(&mut self.world.weapons[i], w)
-                        } else {
-                            self.world.weapons.remove(i)
-                        }
-                    ));
-                    self.cur_pickup = None;
-                }
-            },
-            _ => return,
-        }
+    fn key_up(&mut self, _s: &mut State, _ctx: &mut Context, keycode: Keycode) {
+        self.record(InputEvent::key_up(keycode));
+    }
+    fn mouse_down(&mut self, _s: &mut State, _ctx: &mut Context, btn: MouseButton) {
+        self.record(InputEvent::mouse_down(btn));
+    }
+    fn mouse_up(&mut self, _s: &mut State, _ctx: &mut Context, btn: MouseButton) {
+        self.record(InputEvent::mouse_up(btn));
+    }
+    fn mouse_wheel(&mut self, _s: &mut State, _ctx: &mut Context, y: i32) {
+        self.record(InputEvent::wheel(y));
     }
 }
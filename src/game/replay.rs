@@ -0,0 +1,255 @@
+//! Recording and playing back play sessions
+//!
+//! Every tick the raw input `Play` received is stored as a `Frame`.
+//! Together with the level, what the player carried into it and the seed of the simulation's RNG
+//! this is enough to reproduce a run exactly.
+use std::path::{Path, PathBuf};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter};
+
+use crate::{
+    util::Point2,
+    ext::{InputState, Modifiers, MouseDown},
+    obj::{
+        health::Health,
+        skill::Difficulty,
+        inventory::{Inventory, Slot},
+        weapon::WeaponInstance,
+    },
+};
+use ggez::{
+    GameResult,
+    error::GameError,
+    event::{Keycode, MouseButton},
+};
+
+use ::bincode;
+
+use super::world::{Level, weapon_by_id};
+
+/// File extension of replay files
+pub const EXTENSION: &str = "tdsreplay";
+
+/// The path asked to record to, or a numbered one next to it if it's taken, so earlier replays aren't overwritten
+pub fn unused_path(requested: &Path) -> PathBuf {
+    let stem = requested.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = requested.extension().map(|e| e.to_string_lossy()).unwrap_or_else(|| EXTENSION.into());
+    Some(requested.to_owned()).into_iter()
+        .chain((1..).map(|n| requested.with_file_name(format!("{}-{}.{}", stem, n, ext))))
+        .find(|p| !p.exists())
+        .unwrap()
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+/// A key, mouse button or mouse wheel event
+pub enum InputEvent {
+    KeyDown(i32),
+    KeyUp(i32),
+    MouseDown(u8),
+    MouseUp(u8),
+    /// Steps the wheel was scrolled, up is positive
+    Wheel(i32),
+}
+
+impl InputEvent {
+    #[inline]
+    pub fn key_down(keycode: Keycode) -> Self {
+        InputEvent::KeyDown(keycode as i32)
+    }
+    #[inline]
+    pub fn key_up(keycode: Keycode) -> Self {
+        InputEvent::KeyUp(keycode as i32)
+    }
+    #[inline]
+    pub fn mouse_down(btn: MouseButton) -> Self {
+        InputEvent::MouseDown(button_to_u8(btn))
+    }
+    #[inline]
+    pub fn mouse_up(btn: MouseButton) -> Self {
+        InputEvent::MouseUp(button_to_u8(btn))
+    }
+    #[inline]
+    pub fn wheel(y: i32) -> Self {
+        InputEvent::Wheel(y)
+    }
+    /// The key released by this event if any
+    pub fn released_key(self) -> Option<Keycode> {
+        match self {
+            InputEvent::KeyUp(k) => Keycode::from_i32(k),
+            _ => None,
+        }
+    }
+    /// The mouse button released by this event if any
+    pub fn released_button(self) -> Option<MouseButton> {
+        match self {
+            InputEvent::MouseUp(b) => Some(button_from_u8(b)),
+            _ => None,
+        }
+    }
+    /// Steps the mouse wheel was scrolled by this event if any
+    pub fn scrolled(self) -> Option<i32> {
+        match self {
+            InputEvent::Wheel(y) => Some(y),
+            _ => None,
+        }
+    }
+}
+
+fn button_to_u8(btn: MouseButton) -> u8 {
+    match btn {
+        MouseButton::Unknown => 0,
+        MouseButton::Left => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Right => 3,
+        MouseButton::X1 => 4,
+        MouseButton::X2 => 5,
+    }
+}
+
+fn button_from_u8(n: u8) -> MouseButton {
+    match n {
+        1 => MouseButton::Left,
+        2 => MouseButton::Middle,
+        3 => MouseButton::Right,
+        4 => MouseButton::X1,
+        5 => MouseButton::X2,
+        _ => MouseButton::Unknown,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The input of a single tick
+pub struct Frame {
+    pub input: InputState,
+    pub modifiers: Modifiers,
+    pub mouse_down: MouseDown,
+    #[serde(serialize_with = "crate::io::save::point_ser", deserialize_with = "crate::io::save::point_des")]
+    /// Position of the mouse in world coordinates
+    pub mouse: Point2,
+    /// Key and mouse button events since the last tick
+    pub events: Vec<InputEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A weapon carried into a level, with its state when the level started
+struct CarriedWeapon {
+    id: String,
+    cur_clip: u16,
+    ammo: u16,
+    loading_time: f32,
+    reloading: bool,
+    jerk: f32,
+    jerk_decay: f32,
+    spray_index: usize,
+}
+
+impl CarriedWeapon {
+    fn new(wep: &WeaponInstance<'_>) -> Self {
+        CarriedWeapon {
+            id: wep.weapon.id.clone(),
+            cur_clip: wep.cur_clip,
+            ammo: wep.ammo,
+            loading_time: wep.loading_time,
+            reloading: wep.reloading,
+            jerk: wep.jerk,
+            jerk_decay: wep.jerk_decay,
+            spray_index: wep.spray_index,
+        }
+    }
+    fn restore(&self) -> GameResult<WeaponInstance<'static>> {
+        Ok(WeaponInstance {
+            cur_clip: self.cur_clip,
+            ammo: self.ammo,
+            loading_time: self.loading_time,
+            reloading: self.reloading,
+            jerk: self.jerk,
+            jerk_decay: self.jerk_decay,
+            spray_index: self.spray_index,
+            .. weapon_by_id(&self.id)?.make_instance()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The health and weapons the player carried into a level from the one before
+pub struct Loadout {
+    health: Health,
+    slots: Vec<Option<CarriedWeapon>>,
+    drawn: Slot,
+    switching: f32,
+    ammo: Vec<u16>,
+}
+
+impl Loadout {
+    pub fn new(health: Health, inventory: &Inventory<'_>) -> Self {
+        Loadout {
+            health,
+            slots: inventory.slots.iter().map(|wep| wep.as_ref().map(CarriedWeapon::new)).collect(),
+            drawn: inventory.drawn,
+            switching: inventory.switching,
+            ammo: inventory.ammo.to_vec(),
+        }
+    }
+    pub fn restore(&self) -> GameResult<(Health, Inventory<'static>)> {
+        let mut inventory = Inventory {
+            drawn: self.drawn,
+            switching: self.switching,
+            .. Inventory::default()
+        };
+        for (slot, wep) in inventory.slots.iter_mut().zip(&self.slots) {
+            *slot = match wep {
+                Some(wep) => Some(wep.restore()?),
+                None => None,
+            };
+        }
+        for (ammo, &rounds) in inventory.ammo.iter_mut().zip(&self.ammo) {
+            *ammo = rounds;
+        }
+        Ok((self.health, inventory))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A recorded play session
+pub struct Replay {
+    /// The level as it was played, in the binary level format
+    ///
+    /// Kept with the replay so edits made to the level since don't change the run.
+    pub contents: Vec<u8>,
+    /// What the player carried into the level, if it was continued from another one
+    pub loadout: Option<Loadout>,
+    /// Seed of the simulation's RNG
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub frames: Vec<Frame>,
+}
+
+impl Replay {
+    pub fn new(played: &Level, loadout: Option<Loadout>, seed: u64, difficulty: Difficulty) -> GameResult<Self> {
+        let mut contents = Vec::new();
+        played.write(&mut contents)?;
+        Ok(Replay {
+            contents,
+            loadout,
+            seed,
+            difficulty,
+            frames: Vec::new(),
+        })
+    }
+    /// The level that was played
+    #[inline]
+    pub fn played_level(&self) -> GameResult<Level> {
+        Level::read(&*self.contents)
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> GameResult<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        bincode::deserialize_from(reader)
+            .map_err(|e| GameError::UnknownError(format!("{:?}", e)))
+    }
+    /// Saves the replay, failing if the file already exists
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> GameResult<()> {
+        let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+        bincode::serialize_into(BufWriter::new(file), self)
+            .map_err(|e| GameError::UnknownError(format!("{:?}", e)))
+    }
+}
@@ -0,0 +1,695 @@
+//! The gameplay simulation
+//!
+//! Runs without a `Context` so levels can be played by tests, bots and replays.
+//! Everything that has to be shown or heard is reported back as `Event`s.
+use crate::{
+    util::{angle_to_vec, angle_from_vec, Vector2, Point2},
+    io::snd::Sound,
+    obj::{
+        Object,
+        pickup::Pickup,
+        player::Player,
+        enemy::{Enemy, Chaser},
+        health::Health,
+        skill::Difficulty,
+        decoration::line_of_sight,
+        bullet::{Bullet, Hit, Owner, FriendlyFire, HIT_RADIUS},
+        grenade::Grenade,
+        melee::{in_reach, MELEE_DAMAGE, MELEE_PENETRATION},
+        inventory::{Inventory, Slot},
+        weapon::{WeaponInstance, WeaponDrop, Projectiles},
+    },
+};
+
+use rand::{SeedableRng, rngs::StdRng};
+
+use super::{
+    DELTA,
+    world::{Grid, Level, Statistics, World},
+    path::WalkMap,
+    noise::{Noise, IMPACT_NOISE, BREAK_NOISE, EXPLOSION_NOISE, MELEE_NOISE, SPRINT_NOISE},
+};
+
+#[derive(Debug, Default, Clone)]
+/// What the player does during a single tick
+pub struct Input {
+    /// Walking direction along the x-axis (`-1`, `0` or `1`)
+    pub hor: f32,
+    /// Walking direction along the y-axis (`-1`, `0` or `1`)
+    pub ver: f32,
+    pub sprint: bool,
+    /// The direction the player is looking in
+    pub rot: f32,
+    /// Whether the trigger is held down (used by automatic weapons)
+    pub trigger: bool,
+    /// Fire a single shot, or strike without a weapon
+    pub shoot: bool,
+    /// Strike whatever is in reach
+    pub melee: bool,
+    pub reload: bool,
+    /// Pick up the weapon the player is standing on
+    pub pick_up: bool,
+    /// Draw the weapon in a slot
+    pub switch: Option<Slot>,
+    /// Steps to go through the carried weapons, backwards if negative
+    pub cycle: i32,
+}
+
+#[derive(Debug, Clone)]
+/// Something that happened during a tick
+pub enum Event {
+    /// A sound should be played
+    Sound(Sound),
+    /// A bullet was fired
+    Shot(Object),
+    /// A bullet hit a wall
+    Impact(Object),
+    /// A wall or decoration was shot to pieces
+    Broken(Point2),
+    /// A grenade exploded with this blast radius
+    Explosion(Point2, f32),
+    /// Something enemies can hear
+    Noise(Noise),
+    /// The player was hit by a bullet or blast
+    PlayerHit(Object),
+    /// An enemy was hit by a bullet or blast
+    EnemyHit(Object),
+    /// An enemy died at this position
+    EnemyDeath(Point2),
+    /// The player died
+    Lose,
+    /// The level has been completed
+    Win,
+}
+
+impl Event {
+    #[inline]
+    fn noise(&self) -> Option<Noise> {
+        match *self {
+            Event::Noise(noise) => Some(noise),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An enemy telling the others where it spotted the player
+struct Callout {
+    /// Where the enemy called out from
+    from: Point2,
+    pos: Point2,
+    vel: Vector2,
+    /// Time until the others react
+    delay: f32,
+}
+
+/// The state of a level being played
+pub struct Simulation {
+    pub world: World,
+    /// Index of the weapon drop the player is standing on
+    pub cur_pickup: Option<usize>,
+    pub hits: usize,
+    pub misses: usize,
+    /// Source of all randomness so runs can be reproduced from their seed
+    pub rng: StdRng,
+    /// Noises made after the enemies listened during the last tick
+    unheard: Vec<Noise>,
+    /// Call outs the other enemies haven't reacted to yet
+    callouts: Vec<Callout>,
+    victory_time: f32,
+    initial: (Health, Inventory<'static>),
+    level: Level,
+}
+
+impl Simulation {
+    pub fn new(level: Level, pl: Option<(Health, Inventory<'static>)>, seed: u64, difficulty: Difficulty) -> Self {
+        let mut player = Player::from_point(level.start_point.unwrap_or_else(|| Point2::new(500., 500.)));
+        if let Some((h, inventory)) = pl {
+            player = player.with_health(h).with_inventory(inventory);
+        };
+
+        Simulation {
+            level: level.clone(),
+            initial: (player.health, player.full_inventory()),
+            hits: 0,
+            misses: 0,
+            rng: StdRng::seed_from_u64(seed),
+            unheard: Vec::new(),
+            callouts: Vec::new(),
+            victory_time: 0.,
+            cur_pickup: None,
+            world: {
+                let walkable = WalkMap::new(&level.grid, &level.decorations);
+                let mut world = World {
+                    enemies: level.enemies,
+                    alert: level.alert,
+                    friendly_fire: level.friendly_fire,
+                    bullets: Vec::new(),
+                    grenades: Vec::new(),
+                    weapons: level.weapons,
+                    player,
+                    grid: level.grid,
+                    exit: level.exit,
+                    intels: level.intels,
+                    decorations: level.decorations,
+                    pickups: level.pickups.into_iter().map(|(p, i)| Pickup::new(p, i)).collect(),
+                    walkable,
+                };
+                world.enemy_pickup();
+                world.player_pickup();
+                for enemy in &mut world.enemies {
+                    enemy.pl.health = enemy.archetype().health;
+                    enemy.skill = difficulty.apply(enemy.skill);
+                }
+
+                if world.player.wep.is_none() {
+                    eprintln!("Warning: player has no weapon");
+                }
+
+                for enemy_pos in world.enemies.iter().filter_map(|enemy| if enemy.pl.wep.is_none() {Some(enemy.pl.obj.pos)}else{None}) {
+                    eprintln!("Warning: enemy at {:.2} has no weapon", enemy_pos)
+                }
+
+                world
+            },
+        }
+    }
+    /// Statistics of the run so far
+    pub fn statistics(&self) -> Statistics {
+        Statistics{
+            level: self.level.clone(),
+            hits: self.hits,
+            misses: self.misses,
+            enemies_left: self.world.enemies.len(),
+            health_left: self.world.player.health,
+            inventory: self.world.player.full_inventory(),
+        }
+    }
+    /// Statistics of the run, but with the health and weapons the player started with
+    ///
+    /// Used when the level has to be restarted
+    pub fn initial_statistics(&self) -> Statistics {
+        Statistics{
+            health_left: self.initial.0,
+            inventory: self.initial.1,
+            .. self.statistics()
+        }
+    }
+    fn player_shoot(&mut self, events: &mut Vec<Event>) {
+        if self.world.player.is_switching() {
+            return
+        }
+        if let Some(wep) = &mut self.world.player.wep {
+            let noise = wep.weapon.noise;
+            let muzzle = wep.weapon.muzzle_distance();
+            if let Some(bm) = wep.shoot(events) {
+                let pos = self.world.player.obj.pos + muzzle * angle_to_vec(self.world.player.obj.rot);
+                let mut bul = Object::new(pos);
+                bul.rot = self.world.player.obj.rot;
+
+                events.push(Event::Noise(Noise::new(pos, noise)));
+                match bm.make(bul.clone(), Owner::Player) {
+                    Projectiles::Bullets(bullets) => {
+                        events.push(Event::Shot(bul));
+                        self.world.bullets.extend(bullets);
+                    }
+                    Projectiles::Grenade(grenade) => self.world.grenades.push(grenade),
+                }
+            }
+        }
+    }
+    /// Strikes the closest enemy in reach
+    ///
+    /// Enemies that don't have the player in their vision cone are taken down at once without a sound.
+    fn player_melee(&mut self, events: &mut Vec<Event>) {
+        if !self.world.player.melee_ready() {
+            return
+        }
+        self.world.player.swing();
+        events.push(Event::Sound(Sound::Swing));
+
+        let obj = self.world.player.obj.clone();
+        let grid = &self.world.grid;
+        let target = self.world.enemies.iter()
+            .enumerate()
+            .filter(|(_, enemy)| in_reach(&obj, enemy.pl.obj.pos, grid))
+            .map(|(e, enemy)| (e, (enemy.pl.obj.pos - obj.pos).norm()))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        let e = match target {
+            Some((e, _)) => e,
+            None => return,
+        };
+
+        let enemy = &mut self.world.enemies[e];
+        let pos = enemy.pl.obj.pos;
+        if enemy.faces(obj.pos) {
+            enemy.pl.health.weapon_damage(MELEE_DAMAGE, MELEE_PENETRATION);
+            events.push(Event::Noise(Noise::new(pos, MELEE_NOISE)));
+        } else {
+            enemy.pl.health.kill();
+        }
+        events.push(Event::EnemyHit(Object::with_rot(pos, angle_from_vec(pos - obj.pos))));
+        enemy_hurt(&mut self.world.enemies, &mut self.world.weapons, &mut self.world.bullets, e, obj.pos, events);
+    }
+    /// Blows up a grenade, hurting everything its blast reaches
+    fn explode(&mut self, grenade: &Grenade<'_>, events: &mut Vec<Event>) {
+        let center = grenade.obj.pos;
+        events.push(Event::Explosion(center, grenade.blast_radius()));
+        events.push(Event::Sound(Sound::Explosion));
+        events.push(Event::Noise(Noise::new(center, EXPLOSION_NOISE)));
+
+        let friendly_fire = self.world.friendly_fire;
+        let scale = grenade.owner.damage_scale(Owner::Player, friendly_fire);
+        let pos = self.world.player.obj.pos;
+        if let Some(dmg) = grenade.blast(&self.world.grid, &self.world.decorations, pos).filter(|_| scale > 0.) {
+            grenade.apply_damage(&mut self.world.player.health, scale * dmg);
+            events.push(Event::PlayerHit(Object::with_rot(pos, angle_from_vec(pos - center))));
+            player_hurt(&self.world.player, events);
+        }
+
+        let mut hits = 0;
+        let scale = grenade.owner.damage_scale(Owner::Enemy, friendly_fire);
+        for e in (0..self.world.enemies.len()).rev() {
+            let pos = self.world.enemies[e].pl.obj.pos;
+            if let Some(dmg) = grenade.blast(&self.world.grid, &self.world.decorations, pos).filter(|_| scale > 0.) {
+                grenade.apply_damage(&mut self.world.enemies[e].pl.health, scale * dmg);
+                events.push(Event::EnemyHit(Object::with_rot(pos, angle_from_vec(pos - center))));
+                enemy_hurt(&mut self.world.enemies, &mut self.world.weapons, &mut self.world.bullets, e, center, events);
+                hits += 1;
+            }
+        }
+        // Worked out before any breaks, so debris left behind doesn't change what the others shield
+        let damages: Vec<_> = (0..self.world.decorations.len())
+            .map(|i| grenade.blast_decoration(&self.world.grid, &self.world.decorations, i))
+            .collect();
+        for (decoration, dmg) in self.world.decorations.iter_mut().zip(damages) {
+            if let Some(dmg) = dmg {
+                if decoration.damage(dmg) {
+                    events.push(Event::Broken(decoration.obj.pos));
+                }
+            }
+        }
+
+        // A grenade counts once for every enemy it hurts, or as one miss
+        if grenade.owner == Owner::Player {
+            if hits > 0 {
+                self.hits += hits;
+            } else {
+                self.misses += 1;
+            }
+        }
+    }
+    /// Let enemies react to the call outs whose delay is over
+    fn alert_enemies(&mut self) {
+        for callout in &mut self.callouts {
+            callout.delay -= DELTA;
+        }
+        let (due, waiting): (Vec<_>, Vec<_>) = self.callouts.drain(..).partition(|c| c.delay <= 0.);
+        self.callouts = waiting;
+
+        let alert = self.world.alert;
+        let grid = &self.world.grid;
+        let decorations = &self.world.decorations;
+        for Callout{from, pos, vel, ..} in due {
+            for enemy in &mut self.world.enemies {
+                if enemy.behaviour.chasing() {
+                    continue
+                }
+                let dist = enemy.pl.obj.pos - from;
+                if dist.norm() <= alert.radius && (!alert.line_of_sight || line_of_sight(grid, decorations, from, dist)) {
+                    enemy.behaviour = Chaser::LastKnown{pos, vel};
+                }
+            }
+        }
+    }
+    /// Advance the simulation by one `DELTA`
+    #[allow(clippy::cyclomatic_complexity)]
+    pub fn tick(&mut self, input: &Input) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        self.world.player.obj.rot = input.rot;
+
+        if input.shoot {
+            if self.world.player.wep.is_some() {
+                self.player_shoot(&mut events);
+            } else {
+                self.player_melee(&mut events);
+            }
+        }
+        if input.melee {
+            self.player_melee(&mut events);
+        }
+        if input.reload && !self.world.player.is_switching() {
+            if let Some(wep) = &mut self.world.player.wep {
+                wep.reload(&mut events);
+            }
+        }
+        let mut switched = match input.switch {
+            Some(slot) => self.world.player.switch_to(slot),
+            None => input.cycle != 0 && self.world.player.cycle_weapons(input.cycle),
+        };
+        if input.pick_up {
+            if let Some(i) = self.cur_pickup {
+                let drop = self.world.weapons.remove(i);
+                // Whatever was carried in the same slot is left in its place
+                if let Some(old) = self.world.player.pick_up(WeaponInstance::from_drop(drop)) {
+                    self.world.weapons.insert(i, old.into_drop(self.world.player.obj.pos));
+                }
+                self.cur_pickup = None;
+                switched = true;
+            }
+        }
+        if switched {
+            if let Some(wep) = self.world.player.wep {
+                events.push(Event::Sound(wep.weapon.cock_snd));
+            }
+        }
+
+        let mut deads = Vec::new();
+        // Indexed, since an enemy dying changes the shooters of the other bullets
+        for i in (0..self.world.bullets.len()).rev() {
+            let bullet = &mut self.world.bullets[i];
+            let hit = bullet.update(
+                &mut self.world.grid,
+                &self.world.decorations,
+                &mut self.world.player,
+                &mut *self.world.enemies,
+                self.world.friendly_fire,
+                &mut events,
+            );
+            // Only the player's own shots count towards the statistics
+            let players = bullet.owner == Owner::Player;
+
+            match hit {
+                Hit::None => (),
+                Hit::Wall(x, y) => {
+                    events.push(Event::Sound(bullet.weapon.impact_snd));
+                    let dir = angle_to_vec(bullet.obj.rot);
+                    bullet.obj.pos += Vector2::new(5.*dir.x.signum(), 5.*dir.y.signum());
+                    if self.world.grid.damage(x, y, bullet.damage).is_some() {
+                        events.push(Event::Broken(bullet.obj.pos));
+                        events.push(Event::Noise(Noise::new(bullet.obj.pos, BREAK_NOISE)));
+                    } else {
+                        events.push(Event::Impact(bullet.obj.clone()));
+                        events.push(Event::Noise(Noise::new(bullet.obj.pos, IMPACT_NOISE)));
+                    }
+                    if players {
+                        self.misses += 1;
+                    }
+                    deads.push(i);
+                }
+                Hit::Ricochet(..) => {
+                    events.push(Event::Sound(bullet.weapon.impact_snd));
+                    events.push(Event::Noise(Noise::new(bullet.obj.pos, IMPACT_NOISE)));
+                }
+                Hit::Decoration(d) => {
+                    // The bullet stops at the surface, so the impact is drawn on top of the decoration
+                    events.push(Event::Sound(bullet.weapon.impact_snd));
+                    if self.world.decorations[d].damage(bullet.damage) {
+                        events.push(Event::Broken(bullet.obj.pos));
+                        events.push(Event::Noise(Noise::new(bullet.obj.pos, BREAK_NOISE)));
+                    } else {
+                        events.push(Event::Impact(bullet.obj.clone()));
+                        events.push(Event::Noise(Noise::new(bullet.obj.pos, IMPACT_NOISE)));
+                    }
+                    if players {
+                        self.misses += 1;
+                    }
+                    deads.push(i);
+                }
+                Hit::Player => {
+                    // The player's own ricochets don't count as hits or misses, accuracy is about shooting enemies
+                    deads.push(i);
+                    events.push(Event::PlayerHit(bullet.obj.clone()));
+                    player_hurt(&self.world.player, &mut events);
+                }
+                Hit::Enemy(e) => {
+                    deads.push(i);
+                    if players {
+                        self.hits += 1;
+                    }
+                    events.push(Event::EnemyHit(bullet.obj.clone()));
+                    let from = bullet.obj.pos;
+                    enemy_hurt(&mut self.world.enemies, &mut self.world.weapons, &mut self.world.bullets, e, from, &mut events);
+                }
+            }
+        }
+        for i in deads {
+            self.world.bullets.remove(i);
+        }
+
+        let mut exploded = Vec::new();
+        for (i, grenade) in self.world.grenades.iter_mut().enumerate().rev() {
+            if grenade.update(&self.world.grid, &self.world.decorations, &mut events) {
+                exploded.push(i);
+            }
+        }
+        for i in exploded {
+            let grenade = self.world.grenades.remove(i);
+            self.explode(&grenade, &mut events);
+        }
+        // Whatever broke might open up a new way
+        if events.iter().any(|e| if let Event::Broken(_) = e { true } else { false }) {
+            self.world.refresh_walkable();
+        }
+
+        let mut deads = Vec::new();
+        for (i, &intel) in self.world.intels.iter().enumerate().rev() {
+            if (intel-self.world.player.obj.pos).norm() <= 15. {
+                deads.push(i);
+                events.push(Event::Sound(Sound::Hit));
+            }
+        }
+        for i in deads {
+            self.world.intels.remove(i);
+        }
+        let mut deads = Vec::new();
+        for (i, pickup) in self.world.pickups.iter().enumerate().rev() {
+            if (pickup.pos-self.world.player.obj.pos).norm() <= 15. {
+                pickup.apply(&mut self.world.player);
+                deads.push(i);
+                events.push(Event::Sound(Sound::Hit));
+            }
+        }
+        for i in deads {
+            self.world.pickups.remove(i);
+        }
+        if self.world.scavenge() {
+            events.push(Event::Sound(Sound::Reload));
+        }
+        self.cur_pickup = None;
+        for (i, weapon) in self.world.weapons.iter().enumerate().rev() {
+            if (weapon.pos-self.world.player.obj.pos).norm() <= 29. {
+                self.cur_pickup = Some(i);
+                break
+            }
+        }
+
+        // Define player velocity here already because enemies need it
+        let player_vel = Vector2::new(input.hor, input.ver);
+
+        let audible: Vec<_> = self.unheard.drain(..)
+            .chain(events.iter().filter_map(Event::noise))
+            .map(|noise| (noise.pos, noise.audible_tiles(&self.world.grid)))
+            .collect();
+
+        self.alert_enemies();
+
+        let characters: Vec<_> = self.world.enemies.iter()
+            .map(|enemy| enemy.pl.obj.pos)
+            .chain(Some(self.world.player.obj.pos))
+            .collect();
+
+        for (e, enemy) in self.world.enemies.iter_mut().enumerate() {
+            // Everyone but this enemy, still with the player last
+            let others: Vec<_> = characters.iter()
+                .enumerate()
+                .filter(|&(i, _)| i != e)
+                .map(|(_, &pos)| pos)
+                .collect();
+            let tile = Grid::snap(enemy.pl.obj.pos);
+            if let Some(&(pos, _)) = audible.iter().rev().find(|(_, tiles)| tiles.contains(&tile)) {
+                enemy.hear(pos);
+            }
+            if enemy.can_see(self.world.player.obj.pos, &self.world.grid, &self.world.decorations) {
+                // Only call out when spotting the player, not every tick they stay in sight
+                if !enemy.behaviour.chasing() && enemy.aim.sighted == 0. {
+                    self.callouts.push(Callout {
+                        from: enemy.pl.obj.pos,
+                        pos: self.world.player.obj.pos,
+                        vel: player_vel,
+                        delay: self.world.alert.delay,
+                    });
+                }
+                enemy.spot(self.world.player.obj.pos, player_vel);
+
+                // Don't shoot through allies that would get hurt, the player is the last of the others
+                let pos = enemy.pl.obj.pos;
+                let aim = self.world.player.obj.pos - pos;
+                let ally_in_the_way = self.world.friendly_fire != FriendlyFire::Off && others[..others.len()-1]
+                    .iter()
+                    .any(|&ally| (ally - pos).norm() < aim.norm() && Grid::dist_line_circle(pos, aim, ally) <= HIT_RADIUS);
+
+                let player_pos = self.world.player.obj.pos;
+                let shot = if enemy.out_of_ammo() {
+                    if enemy.melee(player_pos, &self.world.grid) {
+                        events.push(Event::Sound(Sound::Swing));
+                        self.world.player.health.weapon_damage(MELEE_DAMAGE, MELEE_PENETRATION);
+                        events.push(Event::PlayerHit(Object::with_rot(player_pos, angle_from_vec(player_pos - pos))));
+                        events.push(Event::Noise(Noise::new(player_pos, MELEE_NOISE)));
+                        player_hurt(&self.world.player, &mut events);
+                    }
+                    None
+                } else if ally_in_the_way {
+                    None
+                } else {
+                    enemy.shoot(&mut self.rng, &mut events)
+                };
+                match shot {
+                    Some((obj, Projectiles::Bullets(bullets))) => {
+                        events.push(Event::Shot(obj));
+                        self.world.bullets.extend(bullets.into_iter().map(|bullet| Bullet{shooter: Some(e), ..bullet}));
+                    }
+                    Some((_, Projectiles::Grenade(grenade))) => self.world.grenades.push(grenade),
+                    None => (),
+                }
+            } else {
+                enemy.lose_sight();
+            }
+            let before = enemy.pl.obj.pos;
+            enemy.update(&self.world.grid, &self.world.decorations, &self.world.walkable, &others, &mut events);
+            enemy.pl.moved = enemy.pl.obj.pos - before;
+        }
+        let listened = events.len();
+
+        let speed = if input.sprint {
+            200.
+        } else {
+            100.
+        };
+        self.world.player.update(&mut events);
+        let auto_fire = self.world.player.wep
+            .map(|wep| wep.cur_clip > 0 && wep.weapon.fire_mode.is_auto())
+            .unwrap_or(false);
+        if auto_fire && input.trigger {
+            self.player_shoot(&mut events);
+        }
+        let before = self.world.player.obj.pos;
+        if player_vel.norm_squared() != 0. {
+            let displace = player_vel.normalize() * speed * DELTA;
+            let characters: Vec<_> = self.world.enemies.iter().map(|enemy| enemy.pl.obj.pos).collect();
+            self.world.player.obj.move_character(displace, &self.world.grid, &self.world.decorations, &characters);
+        }
+        self.world.player.moved = self.world.player.obj.pos - before;
+        if input.sprint && player_vel != Vector2::new(0., 0.) {
+            events.push(Event::Noise(Noise::new(self.world.player.obj.pos, SPRINT_NOISE)));
+        }
+
+        let game_won = match self.world.exit {
+            Some(p) => self.world.intels.is_empty() && (p - self.world.player.obj.pos).norm() < 32.,
+            None => self.world.enemies.is_empty(),
+        };
+
+        if game_won && self.victory_time <= 0. {
+            events.push(Event::Sound(Sound::Victory));
+            self.victory_time += DELTA;
+        } else if self.victory_time > 0. {
+            self.victory_time += DELTA;
+        }
+        if self.victory_time >= 2. {
+            events.push(Event::Win);
+        }
+
+        self.unheard = events[listened..].iter().filter_map(Event::noise).collect();
+        events
+    }
+}
+
+/// Reports the player getting hurt, or dying
+fn player_hurt(player: &Player, events: &mut Vec<Event>) {
+    events.push(Event::Sound(Sound::Hit));
+    if player.health.is_dead() {
+        events.push(Event::Lose);
+        events.push(Event::Sound(Sound::Death));
+    } else {
+        events.push(Event::Sound(Sound::Hurt));
+    }
+}
+
+/// Reports an enemy getting hurt by something coming from a position
+///
+/// A dead enemy is removed and drops its weapon, one that lives looks where it was hurt from.
+fn enemy_hurt(enemies: &mut Vec<Enemy>, weapons: &mut Vec<WeaponDrop<'static>>, bullets: &mut [Bullet<'static>], e: usize, from: Point2, events: &mut Vec<Event>) {
+    events.push(Event::Sound(Sound::Hit));
+    let enemy = &mut enemies[e];
+    if enemy.pl.health.is_dead() {
+        events.push(Event::Sound(Sound::Death));
+
+        let Enemy{pl: Player{wep, obj: Object{pos, ..}, ..}, ..} = enemies.remove(e);
+        for bullet in bullets {
+            bullet.enemy_removed(e);
+        }
+        events.push(Event::EnemyDeath(pos));
+        if let Some(wep) = wep {
+            weapons.push(wep.into_drop(pos));
+        }
+    } else {
+        if !enemy.behaviour.chasing() {
+            enemy.behaviour = Chaser::LookAround{
+                dir: from - enemy.pl.obj.pos
+            };
+        }
+        events.push(Event::Sound(Sound::Hurt));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::obj::{pickup::pickup_index, weapon::Weapon};
+
+    /// An empty grass level with the player starting at `(100, 100)`
+    fn open_level() -> Level {
+        let mut level = Level::new(20, 20);
+        level.start_point = Some(Point2::new(100., 100.));
+        level
+    }
+
+    #[test]
+    fn walking_into_a_health_pack() {
+        let mut level = open_level();
+        level.pickups.push((Point2::new(200., 100.), pickup_index("health_pack").unwrap()));
+        let hurt = Health{hp: 20., armour: 0.};
+        let mut sim = Simulation::new(level, Some((hurt, Inventory::default())), 0, Difficulty::default());
+
+        let walk = Input{hor: 1., .. Input::default()};
+        let mut ticks = 0;
+        while !sim.world.pickups.is_empty() {
+            sim.tick(&walk);
+            ticks += 1;
+            assert!(ticks < 120, "the player never reached the health pack");
+        }
+        assert_eq!(sim.world.player.health.hp, 100.);
+        assert!(sim.world.player.obj.pos.x > 180.);
+    }
+
+    #[test]
+    fn shooting_an_enemy_dead() {
+        let mut level = open_level();
+        level.weapons.push(Weapon::by_id("magnum").unwrap().make_drop(Point2::new(100., 100.)));
+        // Unarmed and facing away, so it can't fight back
+        level.enemies.push(Enemy::new(Object::new(Point2::new(300., 100.))));
+        let mut sim = Simulation::new(level, None, 0, Difficulty::default());
+        assert!(sim.world.player.wep.is_some());
+
+        let mut events = sim.tick(&Input{shoot: true, .. Input::default()});
+        for _ in 0..30 {
+            events.extend(sim.tick(&Input::default()));
+        }
+
+        assert!(events.iter().any(|e| if let Event::EnemyDeath(_) = e { true } else { false }));
+        assert!(sim.world.enemies.is_empty());
+        assert!(sim.world.bullets.is_empty());
+        assert_eq!((sim.hits, sim.misses), (1, 0));
+    }
+}
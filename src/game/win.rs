@@ -4,7 +4,7 @@ use crate::{
         tex::PosText,
         btn::Button,
     },
-    obj::{health::Health, weapon::WeaponInstance},
+    obj::{health::Health, inventory::Inventory},
 };
 use ggez::{
     Context, GameResult,
@@ -34,7 +34,7 @@ pub struct Win {
     buttons: WinButtons,
     health: Health,
     level: Level,
-    weapon: Option<WeaponInstance<'static>>
+    inventory: Inventory<'static>,
 }
 
 impl Win {
@@ -67,7 +67,7 @@ impl Win {
             health_text,
             level: stats.level,
             health: stats.health_left,
-            weapon: stats.weapon,
+            inventory: stats.inventory,
         }))
     }
     fn restart(&self, s: &mut State) {
@@ -89,7 +89,7 @@ impl Win {
             Content::None | Content::File(_) => return,
         }
 
-        s.switch(StateSwitch::PlayWith{health: self.health, wep: self.weapon, lvl: Box::new(lvl)});
+        s.switch(StateSwitch::PlayWith{health: self.health, inventory: self.inventory, lvl: Box::new(lvl)});
     }
 }
 
@@ -1,14 +1,22 @@
 use crate::{
     util::{Point2, Vector2},
-    io::tex::{Assets, Sprite},
+    io::{
+        tex::{Assets, Sprite},
+        level_text,
+    },
+    game::path::WalkMap,
     obj::{
+        Object,
         player::Player,
-        enemy::Enemy,
+        enemy::{Enemy, AlertSettings, Waypoint},
+        skill::Skill,
         health::Health,
-        bullet::Bullet,
-        weapon::{WeaponInstance, WeaponDrop, WEAPONS},
-        pickup::Pickup,
-        decoration::DecorationObj,
+        bullet::{Bullet, FriendlyFire},
+        grenade::Grenade,
+        weapon::{Weapon, WeaponInstance, WeaponDrop},
+        inventory::Inventory,
+        pickup::{Pickup, PICKUPS, pickup_index},
+        decoration::{DecorationObj, decoration_index},
     }
 };
 use ggez::{
@@ -18,11 +26,12 @@ use ggez::{
 };
 
 use std::path::Path;
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Write, BufRead, BufReader};
+use std::io::{Read, Write, BufRead, BufReader, BufWriter};
 
 use ::bincode;
-use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde::{Serialize, Deserialize, Serializer, Deserializer, de::DeserializeOwned};
 
 #[derive(Debug)]
 /// All the objects in the current world
@@ -32,13 +41,23 @@ pub struct World {
     pub exit: Option<Point2>,
     pub intels: Vec<Point2>,
     pub enemies: Vec<Enemy>,
+    pub alert: AlertSettings,
+    pub friendly_fire: FriendlyFire,
     pub bullets: Vec<Bullet<'static>>,
+    pub grenades: Vec<Grenade<'static>>,
     pub weapons: Vec<WeaponDrop<'static>>,
     pub decorations: Vec<DecorationObj>,
     pub pickups: Vec<Pickup>,
+    /// Which tiles enemies can walk on, has to be refreshed when something breaks
+    pub walkable: WalkMap,
 }
 
 impl World {
+    /// Works out which tiles can be walked on again after tiles or decorations have changed
+    #[inline]
+    pub fn refresh_walkable(&mut self) {
+        self.walkable = WalkMap::new(&self.grid, &self.decorations);
+    }
     pub fn enemy_pickup(&mut self) {
         for enemy in &mut self.enemies {
             let mut dead = None;
@@ -60,24 +79,24 @@ impl World {
             }
             for i in deads.into_iter() {
                 let pickup = self.pickups.remove(i);
-                pickup.apply(&mut enemy.pl.health);
+                pickup.apply(&mut enemy.pl);
             }
         }
     }
     pub fn player_pickup(&mut self) {
         let player = &mut self.player;
-        if player.wep.is_none() {
-            let mut dead = None;
-            for (w, weapon) in self.weapons.iter().enumerate() {
-                if (weapon.pos - player.obj.pos).norm() <= 16. {
-                    dead = Some(w);
-                    break;
-                }
-            }
-            if let Some(i) = dead {
-                player.wep = Some(WeaponInstance::from_drop(self.weapons.remove(i)));
+        let mut dead = None;
+        for (w, weapon) in self.weapons.iter().enumerate() {
+            if (weapon.pos - player.obj.pos).norm() <= 16. && !player.carries(weapon.weapon.slot) {
+                dead = Some(w);
+                break;
             }
         }
+        if let Some(i) = dead {
+            player.pick_up(WeaponInstance::from_drop(self.weapons.remove(i)));
+            // The level starts with the weapon already drawn
+            player.inventory.switching = 0.;
+        }
 
         let mut deads = Vec::new();
         for (p, pickup) in self.pickups.iter().enumerate() {
@@ -88,9 +107,28 @@ impl World {
         }
         for i in deads.into_iter() {
             let pickup = self.pickups.remove(i);
-            pickup.apply(&mut player.health);
+            pickup.apply(player);
         }
     }
+    /// Takes the rounds out of dropped weapons the player stands on that take a calibre the player uses
+    ///
+    /// Emptied thrown weapons are used up. Returns whether any rounds were taken.
+    pub fn scavenge(&mut self) -> bool {
+        let player = &mut self.player;
+        let mut scavenged = false;
+        for drop in &mut self.weapons {
+            let calibre = drop.weapon.calibre;
+            let rounds = drop.cur_clip.saturating_add(drop.ammo);
+            if rounds > 0 && (drop.pos - player.obj.pos).norm() <= 16. && player.uses(calibre) {
+                player.add_ammo(calibre, rounds);
+                drop.cur_clip = 0;
+                drop.ammo = 0;
+                scavenged = true;
+            }
+        }
+        self.weapons.retain(|drop| !drop.weapon.fire_mode.is_thrown() || drop.cur_clip > 0 || drop.ammo > 0);
+        scavenged
+    }
 }
 
 pub struct Statistics {
@@ -99,23 +137,61 @@ pub struct Statistics {
     pub enemies_left: usize,
     pub health_left: Health,
     pub level: Level,
-    pub weapon: Option<WeaponInstance<'static>>,
+    pub inventory: Inventory<'static>,
 }
 
 include!("material_macro.rs");
 
 mat!{
     MISSING = Missing
-    Grass = 0, Grass, false,
-    Wall = 1, Wall, true,
-    Floor = 2, Floor, false,
-    Dirt = 3, Dirt, false,
-    Asphalt = 4, Asphalt, false,
-    Sand = 5, Sand, false,
-    Concrete = 6, Concrete, true,
-    WoodFloor = 7, WoodFloor, false,
-    Stairs = 8, Stairs, false,
-    Missing = 255, Missing, true,
+    Grass = 0, ',', Grass, false,
+    Wall = 1, '#', Wall, true,
+    Floor = 2, '.', Floor, false,
+    Dirt = 3, ':', Dirt, false,
+    Asphalt = 4, '=', Asphalt, false,
+    Sand = 5, '~', Sand, false,
+    Concrete = 6, '%', Concrete, true,
+    WoodFloor = 7, '_', WoodFloor, false,
+    Stairs = 8, '>', Stairs, false,
+    WoodWall = 9, 'H', WoodWall, true,
+    Glass = 10, '|', Glass, true,
+    Crate = 11, 'X', Crate, true,
+    Rubble = 12, '*', Rubble, false,
+    BrokenGlass = 13, '!', BrokenGlass, false,
+    Missing = 255, '?', Missing, true,
+}
+
+impl Material {
+    /// The hit points of materials that can be shot to pieces and what they turn into
+    pub fn destructible(self) -> Option<(f32, Material)> {
+        match self {
+            Material::WoodWall => Some((150., Material::Rubble)),
+            Material::Glass => Some((20., Material::BrokenGlass)),
+            Material::Crate => Some((80., Material::Rubble)),
+            _ => None,
+        }
+    }
+    /// How hard it is for bullets to go through, from `0` to `1`
+    ///
+    /// Bullets with a higher material penetration pass through and lose this fraction of their damage.
+    /// So weapons with a material penetration above `0.1` get through glass, above `0.3` through crates too
+    /// and above `0.45` through wooden walls as well. Nothing gets through walls and concrete.
+    pub fn density(self) -> f32 {
+        match self {
+            Material::Glass => 0.1,
+            Material::Crate => 0.3,
+            Material::WoodWall => 0.45,
+            m if m.solid() => 1.,
+            _ => 0.,
+        }
+    }
+    /// Whether bullets hitting it at a shallow angle glance off
+    pub fn ricochets(self) -> bool {
+        match self {
+            Material::Wall | Material::Concrete => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -123,6 +199,8 @@ pub struct Level {
     pub grid: Grid,
     pub start_point: Option<Point2>,
     pub enemies: Vec<Enemy>,
+    pub alert: AlertSettings,
+    pub friendly_fire: FriendlyFire,
     pub exit: Option<Point2>,
     pub intels: Vec<Point2>,
     pub pickups: Vec<(Point2, u8)>,
@@ -136,6 +214,8 @@ impl Level {
             grid: Grid::new(width, height),
             start_point: None,
             enemies: Vec::new(),
+            alert: AlertSettings::default(),
+            friendly_fire: FriendlyFire::default(),
             exit: None,
             intels: Vec::new(),
             pickups: Vec::new(),
@@ -144,104 +224,143 @@ impl Level {
         }
     }
     pub fn load<P: AsRef<Path>>(path: P) -> GameResult<Self> {
-        let mut reader = BufReader::new(File::open(path)?);
+        if level_text::is_text_file(path.as_ref()) {
+            return level_text::load(path);
+        }
+        Level::read(BufReader::new(File::open(path)?))
+    }
+    /// Reads a level in the binary format
+    pub fn read<R: BufRead>(mut reader: R) -> GameResult<Self> {
         let mut ret = Level::new(0, 0);
 
+        let mut line = String::with_capacity(16);
+        reader.read_line(&mut line)?;
+        // Files without a header are from before the format was versioned
+        // and start with a section right away
+        let version: u16 = if line.starts_with(MAGIC) {
+            let version = line[MAGIC.len()..].trim().parse()
+                .map_err(|_| GameError::UnknownError(format!("Bad level header {:?}", line.trim_right())))?;
+            line.clear();
+            version
+        } else {
+            0
+        };
+        if version > FORMAT_VERSION {
+            eprintln!("Warning: level format version {} is newer than {}", version, FORMAT_VERSION);
+        }
+
         loop {
-            let mut buf = String::with_capacity(16);
-            reader.read_line(&mut buf)?;
-            match &*buf.trim_right() {
+            if line.is_empty() && reader.read_line(&mut line)? == 0 {
+                return Err(GameError::UnknownError("Level file ended without END".to_owned()));
+            }
+            let name = line.trim_right().to_owned();
+            line.clear();
+
+            match &*name {
                 "" => continue,
-                "GRD" => ret.grid = bincode::deserialize_from(&mut reader)
-                .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?,
-                "GRID" => {
-                    let (w, grid): (usize, Vec<u16>) = bincode::deserialize_from(&mut reader)
-                    .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
-                    ret.grid = Grid {
-                        mats: grid.into_iter().map(|n| Material::from(n as u8)).collect(),
-                        width: w as u16
+                "END" => break,
+                // Unversioned sections aren't length-prefixed, so unknown ones can't be skipped
+                name if version == 0 => if !ret.read_section(version, name, &mut reader)? {
+                    return Err(GameError::UnknownError(format!("Bad section {:?}", name)));
+                }
+                name => {
+                    let len: u64 = deserialize(&mut reader)?;
+                    let mut payload = vec![0; len as usize];
+                    reader.read_exact(&mut payload)?;
+                    if !ret.read_section(version, name, &*payload)? {
+                        eprintln!("Warning: skipping unknown level section {:?}", name);
                     }
                 }
-                "START" => ret.start_point = Some(
-                    bincode::deserialize_from(&mut reader)
-                        .map(|(x, y)| Point2::new(x, y))
-                        .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?
-                ),
-                "ENEMIES" => ret.enemies = bincode::deserialize_from(&mut reader)
-                    .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?,
-                "POINT GOAL" => ret.exit = Some(bincode::deserialize_from(&mut reader)
-                    .map(|(x, y)| Point2::new(x, y))
-                    .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?),
-                "INTELS" => ret.intels = bincode::deserialize_from(&mut reader)
-                    .map(|l: Vec<(f32, f32)>| l.into_iter().map(|(x, y)| Point2::new(x, y)).collect())
-                    .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?,
-                "DECORATIONS" => ret.decorations = bincode::deserialize_from(&mut reader)
-                    .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?,
-                "PICKUPS" => ret.pickups = bincode::deserialize_from(&mut reader)
-                    .map(|l: Vec<((f32, f32), u8)>| l.into_iter().map(|((x, y), i)| (Point2::new(x, y), i)).collect())
-                    .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?,
-                "WEAPONS" => ret.weapons = bincode::deserialize_from(&mut reader)
-                    .map(|l: Vec<((f32, f32), u8)>| l.into_iter().map(|((x, y), i)| WEAPONS[i as usize].make_drop(Point2::new(x, y))).collect())
-                    .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?,
-                "END" => break,
-                _ => return Err("Bad section".to_string())?
             }
         }
 
         Ok(ret)
     }
+    /// Reads the payload of a section into the level
+    ///
+    /// Returns `false` if the section is unknown.
+    fn read_section<R: Read>(&mut self, version: u16, name: &str, mut reader: R) -> GameResult<bool> {
+        match name {
+            "GRD" => self.grid = deserialize(&mut reader)?,
+            "GRID" => self.grid = migrate_grid(deserialize(&mut reader)?),
+            "START" => self.start_point = Some(
+                deserialize(&mut reader).map(|(x, y)| Point2::new(x, y))?
+            ),
+            "ENEMIES" if version < 3 => self.enemies = deserialize::<_, Vec<EnemyV2>>(&mut reader)?
+                .into_iter()
+                .map(|EnemyV2{pl}| Enemy::new(pl.obj))
+                .collect(),
+            "ENEMIES" if version < 4 => self.enemies = deserialize::<_, Vec<EnemyV3>>(&mut reader)?
+                .into_iter()
+                .map(|EnemyV3{pl, patrol}| Enemy{patrol, .. Enemy::new(pl.obj)})
+                .collect(),
+            "ENEMIES" if version < 5 => self.enemies = deserialize::<_, Vec<EnemyV4>>(&mut reader)?
+                .into_iter()
+                .map(|EnemyV4{pl, patrol, skill}| Enemy{patrol, skill, .. Enemy::new(pl.obj)})
+                .collect(),
+            "ENEMIES" => self.enemies = deserialize(&mut reader)?,
+            "ALERT" => self.alert = deserialize(&mut reader)?,
+            "FRIENDLY FIRE" => self.friendly_fire = deserialize(&mut reader)?,
+            "POINT GOAL" => self.exit = Some(deserialize(&mut reader).map(|(x, y)| Point2::new(x, y))?),
+            "INTELS" => self.intels = deserialize(&mut reader)
+                .map(|l: Vec<(f32, f32)>| l.into_iter().map(|(x, y)| Point2::new(x, y)).collect())?,
+            "DECORATIONS" if version < 2 => self.decorations = migrate_decorations(deserialize(&mut reader)?)?,
+            "DECORATIONS" => self.decorations = deserialize(&mut reader)?,
+            "PICKUPS" if version < 2 => self.pickups = migrate_indices(deserialize(&mut reader)?, LEGACY_PICKUPS)?
+                .into_iter()
+                .map(|(p, id)| Ok((p, pickup_by_id(id)?)))
+                .collect::<GameResult<_>>()?,
+            "PICKUPS" => self.pickups = deserialize::<_, Vec<((f32, f32), String)>>(&mut reader)?
+                .into_iter()
+                .map(|((x, y), id)| Ok((Point2::new(x, y), pickup_by_id(&id)?)))
+                .collect::<GameResult<_>>()?,
+            "WEAPONS" if version < 2 => self.weapons = migrate_indices(deserialize(&mut reader)?, LEGACY_WEAPONS)?
+                .into_iter()
+                .map(|(p, id)| Ok(weapon_by_id(id)?.make_drop(p)))
+                .collect::<GameResult<_>>()?,
+            "WEAPONS" => self.weapons = deserialize::<_, Vec<((f32, f32), String)>>(&mut reader)?
+                .into_iter()
+                .map(|((x, y), id)| Ok(weapon_by_id(&id)?.make_drop(Point2::new(x, y))))
+                .collect::<GameResult<_>>()?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
     pub fn save<P: AsRef<Path>>(&self, path: P) -> GameResult<()> {
-        let mut file = File::create(path)?;
-
-        writeln!(file, "GRD")?;
-        bincode::serialize_into(&mut file, &self.grid)
-            .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+        if level_text::is_text_file(path.as_ref()) {
+            return level_text::save(self, path);
+        }
+        self.write(BufWriter::new(File::create(path)?))
+    }
+    /// Writes the level in the binary format
+    pub fn write<W: Write>(&self, mut file: W) -> GameResult<()> {
+        writeln!(file, "{} {}", MAGIC, FORMAT_VERSION)?;
+        write_section(&mut file, "GRD", &self.grid)?;
         if let Some(start) = self.start_point {
-            writeln!(file, "\nSTART")?;
-            bincode::serialize_into(&mut file, &(start.x, start.y))
-            .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+            write_section(&mut file, "START", &(start.x, start.y))?;
         }
         if !self.enemies.is_empty() {
-            writeln!(file, "\nENEMIES")?;
-            bincode::serialize_into(&mut file, &self.enemies)
-            .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+            write_section(&mut file, "ENEMIES", &self.enemies)?;
         }
+        write_section(&mut file, "ALERT", &self.alert)?;
+        write_section(&mut file, "FRIENDLY FIRE", &self.friendly_fire)?;
         if let Some(p) = self.exit {
-            writeln!(file, "\nPOINT GOAL")?;
-            bincode::serialize_into(&mut file, &(p.x, p.y))
-            .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+            write_section(&mut file, "POINT GOAL", &(p.x, p.y))?;
         }
         if !self.intels.is_empty() {
-            writeln!(file, "\nINTELS")?;
             let intels: Vec<_> = self.intels.iter().map(|p| (p.x, p.y)).collect();
-            bincode::serialize_into(&mut file, &intels)
-                .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+            write_section(&mut file, "INTELS", &intels)?;
         }
         if !self.decorations.is_empty() {
-            writeln!(file, "\nDECORATIONS")?;
-            bincode::serialize_into(&mut file, &self.decorations)
-            .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+            write_section(&mut file, "DECORATIONS", &self.decorations)?;
         }
         if !self.pickups.is_empty() {
-            writeln!(file, "\nPICKUPS")?;
-            let pickups: Vec<_> = self.pickups.iter().map(|&(p, i)| ((p.x, p.y), i)).collect();
-            bincode::serialize_into(&mut file, &pickups)
-                .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+            let pickups: Vec<_> = self.pickups.iter().map(|&(p, i)| ((p.x, p.y), PICKUPS[i as usize].id)).collect();
+            write_section(&mut file, "PICKUPS", &pickups)?;
         }
         if !self.weapons.is_empty() {
-            writeln!(file, "\nWEAPONS")?;
-            let pickups: Vec<((f32, f32), u8)> = self.weapons.iter().map(|w| ((w.pos.x, w.pos.y), {
-                let mut index = 0;
-                for (i, wep) in WEAPONS.iter().enumerate() {
-                    if wep.name == w.weapon.name {
-                        index = i;
-                        break
-                    }  
-                }
-                index as u8
-            })).collect();
-            bincode::serialize_into(&mut file, &pickups)
-                .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+            let weapons: Vec<_> = self.weapons.iter().map(|w| ((w.pos.x, w.pos.y), w.weapon.id.as_str())).collect();
+            write_section(&mut file, "WEAPONS", &weapons)?;
         }
 
         writeln!(file, "\nEND")?;
@@ -249,10 +368,113 @@ impl Level {
     }
 }
 
+/// The first line of a level file starts with this followed by the format version
+const MAGIC: &str = "TDS LEVEL";
+/// Version of the level format written by `Level::save`
+///
+/// * 0: No header, sections aren't length-prefixed
+/// * 1: Header and length-prefixed sections
+/// * 2: Weapons, pickups and decorations are referred to by their identifiers instead of indices
+/// * 3: Enemies have patrol routes
+/// * 4: Enemies have a shooting skill
+/// * 5: Enemies have a type
+pub const FORMAT_VERSION: u16 = 5;
+
+#[inline]
+fn deserialize<R: Read, T: DeserializeOwned>(reader: R) -> GameResult<T> {
+    bincode::deserialize_from(reader)
+        .map_err(|e| GameError::UnknownError(format!("{:?}", e)))
+}
+
+/// Writes the section name followed by the length of the payload and the payload itself
+fn write_section<W: Write, T: Serialize>(w: &mut W, name: &str, payload: &T) -> GameResult<()> {
+    let payload = bincode::serialize(payload)
+        .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+    writeln!(w, "\n{}", name)?;
+    bincode::serialize_into(&mut *w, &(payload.len() as u64))
+        .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+    w.write_all(&payload)?;
+    Ok(())
+}
+
+/// Upgrades the old `GRID` section which stored materials as `u16`s
+fn migrate_grid((width, mats): (usize, Vec<u16>)) -> Grid {
+    Grid {
+        mats: mats.into_iter().map(|n| Material::from(n as u8)).collect(),
+        width: width as u16,
+        damage_taken: HashMap::new(),
+    }
+}
+
+/// Identifiers of the weapons, pickups and decorations in the order
+/// their indices referred to before format version 2
+const LEGACY_WEAPONS: &[&str] = &["glock", "five_seven", "magnum", "m4a1", "ak47", "arwp"];
+const LEGACY_PICKUPS: &[&str] = &["health_pack", "armour", "adrenaline"];
+const LEGACY_DECORATIONS: &[&str] = &[
+    "chair1", "chair2", "chair_boss", "lamp_post", "officeplant", "officeplant2", "officeplant3", "trashcan",
+    "manhole_cover", "manhole_cover2", "desk_lamp", "wall_light", "wall_light2", "wall_light3", "road_mark",
+];
+
+/// Upgrades a list of positions with legacy indices to positions with identifiers
+fn migrate_indices(l: Vec<((f32, f32), u8)>, ids: &[&'static str]) -> GameResult<Vec<(Point2, &'static str)>> {
+    l.into_iter()
+        .map(|((x, y), i)| ids.get(i as usize)
+            .map(|&id| (Point2::new(x, y), id))
+            .ok_or_else(|| GameError::UnknownError(format!("Unknown index {} in level", i))))
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct LegacyDecorationObj {
+    obj: Object,
+    decl: usize,
+}
+
+/// Upgrades decorations that refer to their declaration by index
+fn migrate_decorations(l: Vec<LegacyDecorationObj>) -> GameResult<Vec<DecorationObj>> {
+    l.into_iter()
+        .map(|LegacyDecorationObj{obj, decl}| {
+            let id = LEGACY_DECORATIONS.get(decl)
+                .ok_or_else(|| GameError::UnknownError(format!("Unknown decoration index {} in level", decl)))?;
+            Ok(DecorationObj::new(obj, decoration_by_id(id)?))
+        })
+        .collect()
+}
+
+/// Enemies as they were stored in each format version
+#[derive(Deserialize)]
+struct EnemyV2 {
+    pl: Player,
+}
+#[derive(Deserialize)]
+struct EnemyV3 {
+    pl: Player,
+    patrol: Vec<Waypoint>,
+}
+#[derive(Deserialize)]
+struct EnemyV4 {
+    pl: Player,
+    patrol: Vec<Waypoint>,
+    skill: Skill,
+}
+
+pub(crate) fn weapon_by_id(id: &str) -> GameResult<&'static Weapon> {
+    Weapon::by_id(id).ok_or_else(|| GameError::UnknownError(format!("Unknown weapon {:?}", id)))
+}
+pub(crate) fn pickup_by_id(id: &str) -> GameResult<u8> {
+    pickup_index(id).ok_or_else(|| GameError::UnknownError(format!("Unknown pickup {:?}", id)))
+}
+fn decoration_by_id(id: &str) -> GameResult<usize> {
+    decoration_index(id).ok_or_else(|| GameError::UnknownError(format!("Unknown decoration {:?}", id)))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Grid{
     width: u16,
     mats: Vec<Material>,
+    #[serde(skip)]
+    /// Damage taken by destructible tiles, by index
+    damage_taken: HashMap<usize, f32>,
 }
 
 impl Grid {
@@ -260,8 +482,44 @@ impl Grid {
         Grid {
             width,
             mats: vec![Material::Grass; (width*height) as usize],
+            damage_taken: HashMap::new(),
         }
     }
+    /// Makes a grid from rows of material characters
+    pub fn from_rows<S: AsRef<str>>(rows: &[S]) -> Result<Self, String> {
+        let width = rows.first().map(|row| row.as_ref().chars().count()).unwrap_or(0);
+        if width == 0 {
+            return Err("Grid has no tiles".to_owned());
+        }
+        let mut mats = Vec::with_capacity(width * rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            let len = mats.len();
+            for (x, c) in row.as_ref().chars().enumerate() {
+                match Material::try_from_char(c) {
+                    Some(mat) => mats.push(mat),
+                    None => return Err(format!("Unknown material {:?} at row {} column {}", c, y, x)),
+                }
+            }
+            if mats.len() - len != width {
+                return Err(format!("Row {} of the grid isn't {} tiles wide", y, width));
+            }
+        }
+        Ok(Grid {
+            width: width as u16,
+            mats,
+            damage_taken: HashMap::new(),
+        })
+    }
+    /// The grid as rows of material characters
+    pub fn rows(&self) -> Vec<String> {
+        if self.width == 0 {
+            return Vec::new();
+        }
+        self.mats
+            .chunks(self.width as usize)
+            .map(|row| row.iter().map(|m| m.to_char()).collect())
+            .collect()
+    }
     #[inline]
     pub fn width(&self) -> u16 {
         self.width
@@ -341,11 +599,31 @@ impl Grid {
             }
         }
     }
+    /// Damages a destructible tile and returns what it turned into if it broke
+    pub fn damage(&mut self, x: u16, y: u16, dmg: f32) -> Option<Material> {
+        let (hp, broken) = self.get(x, y)?.destructible()?;
+        let i = self.idx(x, y);
+        let taken = self.damage_taken.entry(i).or_insert(0.);
+        *taken += dmg;
+        if *taken >= hp {
+            self.damage_taken.remove(&i);
+            self.mats[i] = broken;
+            Some(broken)
+        } else {
+            None
+        }
+    }
+    /// Casts a ray until it reaches a solid tile
     pub fn ray_cast(&self, from: Point2, dist: Vector2, finite: bool) -> RayCast {
+        self.cross(from, dist, finite, |crossing| crossing.mat.solid())
+    }
+    /// Casts a ray and reports every tile it crosses to `stop` until it returns `true`
+    pub fn cross<F: FnMut(Crossing) -> bool>(&self, from: Point2, dist: Vector2, finite: bool, mut stop: F) -> RayCast {
         let dest = from + dist;
 
         let mut cur = from;
         let (mut gx, mut gy) = Self::snap(cur);
+        let mut normal = Vector2::new(0., 0.);
         let x_dir = Direction::new(dist.x);
         let y_dir = Direction::new(dist.y);
 
@@ -357,7 +635,7 @@ impl Grid {
             let mat = self.get(gx, gy);
 
             if let Some(mat) = mat {
-                if mat.solid() {
+                if stop(Crossing{tile: (gx, gy), mat, entry: cur, normal}) {
                     break RayCast::Half(cur);
                 }
                 if cur.x < 0. || cur.y < 0. {
@@ -376,6 +654,7 @@ impl Grid {
                 // Going along x
                 cur.x = nearest_corner.x;
                 cur.y += time.0 * dist.y;
+                normal = Vector2::new(-x_dir.sign(), 0.);
 
                 gx = if let Some(n) = x_dir.on_u16(gx) {
                     n
@@ -386,6 +665,7 @@ impl Grid {
                 // Going along y
                 cur.y = nearest_corner.y;
                 cur.x += time.1 * dist.x;
+                normal = Vector2::new(0., -y_dir.sign());
 
                 gy = if let Some(n) = y_dir.on_u16(gy) {
                     n
@@ -456,6 +736,24 @@ impl Direction {
             Direction::Neg => n,
         }
     }
+    #[inline]
+    fn sign(self) -> f32 {
+        match self {
+            Direction::Pos => 1.,
+            Direction::Neg => -1.,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+/// A tile crossed by a ray
+pub struct Crossing {
+    pub tile: (u16, u16),
+    pub mat: Material,
+    /// Where the ray enters the tile
+    pub entry: Point2,
+    /// Normal of the face the ray enters through, zero for the tile the ray starts in
+    pub normal: Vector2,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -0,0 +1,97 @@
+//! Human readable level files
+//!
+//! Levels whose file name ends in `.ron` are stored as RON text instead of bincode sections,
+//! so they can be diffed and reviewed. The grid is written as rows of material characters.
+use std::path::Path;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use crate::{
+    util::Point2,
+    game::world::{Grid, Level, FORMAT_VERSION, weapon_by_id, pickup_by_id},
+    obj::{enemy::{Enemy, AlertSettings}, bullet::FriendlyFire, decoration::DecorationObj, pickup::PICKUPS},
+};
+use ggez::{GameResult, error::GameError};
+
+use ::ron;
+
+/// File extension of text level files
+pub const EXTENSION: &str = "ron";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct TextLevel {
+    version: u16,
+    grid: Vec<String>,
+    start: Option<(f32, f32)>,
+    exit: Option<(f32, f32)>,
+    intels: Vec<(f32, f32)>,
+    enemies: Vec<Enemy>,
+    alert: AlertSettings,
+    friendly_fire: FriendlyFire,
+    pickups: Vec<((f32, f32), String)>,
+    decorations: Vec<DecorationObj>,
+    weapons: Vec<((f32, f32), String)>,
+}
+
+#[inline]
+/// Whether the file at the path should be stored as text
+pub fn is_text_file(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some(EXTENSION)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> GameResult<Level> {
+    let mut text = String::new();
+    File::open(path)?.read_to_string(&mut text)?;
+
+    let lvl: TextLevel = ron::de::from_str(&text)
+        .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+    if lvl.version > FORMAT_VERSION {
+        eprintln!("Warning: level format version {} is newer than {}", lvl.version, FORMAT_VERSION);
+    }
+
+    let mut pickups = Vec::with_capacity(lvl.pickups.len());
+    for ((x, y), id) in lvl.pickups {
+        pickups.push((Point2::new(x, y), pickup_by_id(&id)?));
+    }
+    let mut weapons = Vec::with_capacity(lvl.weapons.len());
+    for ((x, y), id) in lvl.weapons {
+        weapons.push(weapon_by_id(&id)?.make_drop(Point2::new(x, y)));
+    }
+
+    Ok(Level {
+        grid: Grid::from_rows(&lvl.grid).map_err(GameError::UnknownError)?,
+        start_point: lvl.start.map(|(x, y)| Point2::new(x, y)),
+        enemies: lvl.enemies,
+        alert: lvl.alert,
+        friendly_fire: lvl.friendly_fire,
+        exit: lvl.exit.map(|(x, y)| Point2::new(x, y)),
+        intels: lvl.intels.into_iter().map(|(x, y)| Point2::new(x, y)).collect(),
+        pickups,
+        decorations: lvl.decorations,
+        weapons,
+    })
+}
+
+pub fn save<P: AsRef<Path>>(level: &Level, path: P) -> GameResult<()> {
+    let lvl = TextLevel {
+        version: FORMAT_VERSION,
+        grid: level.grid.rows(),
+        start: level.start_point.map(|p| (p.x, p.y)),
+        enemies: level.enemies.clone(),
+        alert: level.alert,
+        friendly_fire: level.friendly_fire,
+        exit: level.exit.map(|p| (p.x, p.y)),
+        intels: level.intels.iter().map(|p| (p.x, p.y)).collect(),
+        pickups: level.pickups.iter().map(|&(p, i)| ((p.x, p.y), PICKUPS[i as usize].id.to_owned())).collect(),
+        decorations: level.decorations.clone(),
+        weapons: level.weapons.iter().map(|w| ((w.pos.x, w.pos.y), w.weapon.id.clone())).collect(),
+    };
+
+    let text = ron::ser::to_string_pretty(&lvl, Default::default())
+        .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+
+    let mut file = File::create(path)?;
+    writeln!(file, "{}", text)?;
+    Ok(())
+}
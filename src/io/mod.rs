@@ -2,3 +2,4 @@ pub mod tex;
 pub mod save;
 pub mod snd;
 pub mod btn;
+pub mod level_text;
@@ -51,7 +51,7 @@ macro_rules! sounds {
         $snd:expr,
         $typ:ident,
     )*) => (
-        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
         pub enum Sound {
             $($name,)*
         }
@@ -147,6 +147,7 @@ sounds! {
     Shot2, "shot2", Wave,
     Cock, "cock", Wave,
     Cock2, "cock2", Wave,
+    Pump, "pump", Wave,
     CockAk47, "cock_ak47", Wave,
     Reload, "reload", Wave,
     ReloadM4, "reload_m4", Wave,
@@ -154,6 +155,9 @@ sounds! {
     ClickPistol, "click_pistol", Wave,
     ClickUzi, "click_uzi", Wave,
     Impact, "impact", Wave,
+    Explosion, "explosion", Wave,
+    Throw, "throw", Wave,
+    Swing, "swing", Wave,
     Hit, "hit", Wave,
     Hurt, "hurt", Wave,
     Death, "death", Wave,
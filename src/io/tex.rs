@@ -72,6 +72,9 @@ macro_rules! sprites {
 sprites! {
     Player, "common/player", 32., 32.,
     Enemy, "common/enemy", 32., 32.,
+    EnemyHeavy, "common/enemy_heavy", 32., 32.,
+    EnemySniper, "common/enemy_sniper", 32., 32.,
+    EnemyRusher, "common/enemy_rusher", 32., 32.,
     Crosshair, "common/crosshair", 32., 32.,
     Wall, "materials/wall", 32., 32.,
     Grass, "materials/grass", 32., 32.,
@@ -84,6 +87,10 @@ sprites! {
     Stairs, "materials/stairs", 32., 32.,
     Sidewalk, "materials/sidewalk", 32., 32.,
     WoodWall, "materials/wood_wall", 32., 32.,
+    Glass, "materials/glass", 32., 32.,
+    BrokenGlass, "materials/broken_glass", 32., 32.,
+    Crate, "materials/crate", 32., 32.,
+    Rubble, "materials/rubble", 32., 32.,
     Missing, "materials/missing", 32., 32.,
     Bullet, "common/bullet", 16., 16.,
     Hole, "common/hole", 8., 8.,
@@ -95,6 +102,13 @@ sprites! {
     HealthPack, "pickups/health_pack", 32., 32.,
     Armour, "pickups/armour", 32., 32.,
     Adrenaline, "pickups/adrenaline", 32., 32.,
+    Ammo9mm, "pickups/ammo_9mm", 32., 32.,
+    Ammo57, "pickups/ammo_57", 32., 32.,
+    Ammo50, "pickups/ammo_50", 32., 32.,
+    Ammo556, "pickups/ammo_556", 32., 32.,
+    Ammo762, "pickups/ammo_762", 32., 32.,
+    AmmoShells, "pickups/ammo_shells", 32., 32.,
+    AmmoFrag, "pickups/ammo_frag", 32., 32.,
     Trashcan, "decorations/trashcan", 32., 32.,
     LampPost, "decorations/lamp_post", 32., 32.,
     Chair1, "decorations/chair1", 32., 32.,
@@ -114,6 +128,7 @@ sprites! {
     Machinery2, "decorations/machinery2", 32., 32.,
     Machinery3, "decorations/machinery3", 32., 32.,
     Machinery4, "decorations/machinery4", 32., 32.,
+    Debris, "decorations/debris", 32., 32.,
     Glock, "weapons/glock", 32., 32.,
     GlockHands, "weapons/glock_hands", 32., 32.,
     FiveSeven, "weapons/five_seven", 32., 32.,
@@ -126,6 +141,10 @@ sprites! {
     MagnumHands, "weapons/magnum_hands", 32., 32.,
     Arwp, "weapons/arwp", 64., 32.,
     ArwpHands, "weapons/arwp_hands", 32., 32.,
+    Shotgun, "weapons/shotgun", 32., 32.,
+    ShotgunHands, "weapons/shotgun_hands", 32., 32.,
+    Pineapple, "weapons/pineapple", 32., 32.,
+    PineappleHands, "weapons/pineapple_hands", 32., 32.,
 }
 
 impl Assets {
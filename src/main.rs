@@ -4,8 +4,11 @@
 
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
+extern crate lazy_static;
 
 use std::env::args;
+use std::path::PathBuf;
 
 use ggez::{
     ContextBuilder,
@@ -41,10 +44,40 @@ pub mod util {
     }
 }
 
-use self::game::Master;
+use self::game::{Master, world::Level};
+use self::obj::skill::Difficulty;
 
 fn main() {
-    let mut args = args().skip(1);
+    let mut args: Vec<String> = args().skip(1).collect();
+
+    // The difficulty and where to record replays to can be given before any other argument
+    let mut difficulty = Difficulty::default();
+    let mut record = None;
+    loop {
+        match args.first().map(String::as_str) {
+            Some("--difficulty") => {
+                let name = if args.len() > 1 { args.remove(1) } else { String::new() };
+                args.remove(0);
+                difficulty = match Difficulty::from_name(&name) {
+                    Some(d) => d,
+                    None => {
+                        eprintln!("Unknown difficulty {:?}, expected easy, normal or hard", name);
+                        return
+                    }
+                };
+            }
+            Some("--record") => {
+                if args.len() < 2 {
+                    eprintln!("Usage: tds --record <replay> [level]");
+                    return
+                }
+                record = Some(PathBuf::from(args.remove(1)));
+                args.remove(0);
+            }
+            _ => break,
+        }
+    }
+    let mut args = args.into_iter();
 
     let arg;
     if let Some(p) = args.next() {
@@ -53,6 +86,21 @@ fn main() {
         arg = "".to_owned();
     };
 
+    // Convert a level between the binary and text formats without starting the game
+    if arg == "--convert" {
+        let (from, to) = match (args.next(), args.next()) {
+            (Some(from), Some(to)) => (from, to),
+            _ => {
+                eprintln!("Usage: tds --convert <from> <to>");
+                return
+            }
+        };
+        if let Err(e) = Level::load(&from).and_then(|lvl| lvl.save(&to)) {
+            eprintln!("Couldn't convert level {}", e);
+        }
+        return
+    }
+
     // Set window mode
     let window_mode = conf::WindowMode::default().dimensions(1152, 648);
 
@@ -71,7 +119,13 @@ fn main() {
     }
 
     // Tries to create a game state and runs it if succesful
-    match Master::new(&mut ctx, &arg) {
+    let game = if arg == "--replay" {
+        let path = args.next().unwrap_or_default();
+        Master::replay(&mut ctx, &path)
+    } else {
+        Master::new(&mut ctx, &arg, difficulty, record)
+    };
+    match game {
         Err(e) => {
             eprintln!("Couldn't load game {}", e);
         }
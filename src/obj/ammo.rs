@@ -0,0 +1,45 @@
+//! Ammunition shared between weapons of the same calibre
+
+/// Number of calibres
+pub const CALIBRES: usize = 7;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The kind of rounds a weapon takes
+pub enum Calibre {
+    Mm9,
+    Mm57,
+    Cal50,
+    Mm556,
+    Mm762,
+    Gauge12,
+    /// Hand grenades
+    Frag,
+}
+
+impl Calibre {
+    pub const ALL: [Calibre; CALIBRES] = [
+        Calibre::Mm9,
+        Calibre::Mm57,
+        Calibre::Cal50,
+        Calibre::Mm556,
+        Calibre::Mm762,
+        Calibre::Gauge12,
+        Calibre::Frag,
+    ];
+
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Calibre::Mm9 => "9mm",
+            Calibre::Mm57 => "5.7mm",
+            Calibre::Cal50 => ".50",
+            Calibre::Mm556 => "5.56mm",
+            Calibre::Mm762 => "7.62mm",
+            Calibre::Gauge12 => "12 gauge",
+            Calibre::Frag => "frag",
+        }
+    }
+}
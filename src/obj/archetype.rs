@@ -0,0 +1,92 @@
+//! The different types of enemies
+use std::f32::consts::PI;
+
+use crate::io::tex::Sprite;
+use serde::{Serialize, Deserialize, Serializer, Deserializer, de::Error};
+
+use super::{health::Health, skill::Skill};
+
+#[derive(Debug, Copy, Clone)]
+pub struct Archetype {
+    /// Identifier used to refer to the type in level files
+    pub id: &'static str,
+    pub spr: Sprite,
+    /// Health the enemy starts with
+    pub health: Health,
+    /// Speed when chasing the player
+    pub speed: f32,
+    /// Half the angle of the vision cone
+    pub vision: f32,
+    /// Whether the enemy stays where it is instead of going after the player
+    pub holds_position: bool,
+    /// Health below which the enemy takes cover
+    pub cover_health: f32,
+    /// The skill enemies of this type are placed with
+    pub skill: Skill,
+}
+
+pub const ARCHETYPES: &[Archetype] = &[
+    Archetype {
+        id: "guard",
+        spr: Sprite::Enemy,
+        health: Health{hp: 100., armour: 0.},
+        speed: 100.,
+        vision: PI / 4.,
+        holds_position: false,
+        cover_health: 40.,
+        skill: Skill::REGULAR,
+    },
+    Archetype {
+        id: "heavy",
+        spr: Sprite::EnemyHeavy,
+        health: Health{hp: 150., armour: 100.},
+        speed: 70.,
+        vision: PI / 5.,
+        holds_position: false,
+        cover_health: 20.,
+        skill: Skill {
+            burst_length: 12,
+            .. Skill::ROOKIE
+        },
+    },
+    Archetype {
+        id: "sniper",
+        spr: Sprite::EnemySniper,
+        health: Health{hp: 80., armour: 0.},
+        speed: 80.,
+        vision: PI / 8.,
+        holds_position: true,
+        cover_health: 0.,
+        skill: Skill {
+            burst_length: 1,
+            burst_pause: 1.5,
+            .. Skill::VETERAN
+        },
+    },
+    Archetype {
+        id: "rusher",
+        spr: Sprite::EnemyRusher,
+        health: Health{hp: 70., armour: 0.},
+        speed: 160.,
+        vision: PI / 3.,
+        holds_position: false,
+        cover_health: 0.,
+        skill: Skill::ROOKIE,
+    },
+];
+
+/// Looks up the index in `ARCHETYPES` of an enemy type by its identifier
+pub fn archetype_index(id: &str) -> Option<usize> {
+    ARCHETYPES.iter().position(|a| a.id == id)
+}
+
+/// Serialize the index of an enemy type as its identifier
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub fn archetype_ser<S: Serializer>(archetype: &usize, ser: S) -> Result<S::Ok, S::Error> {
+    ARCHETYPES[*archetype].id.serialize(ser)
+}
+/// Deserialize the identifier of an enemy type into its index
+pub fn archetype_des<'de, D: Deserializer<'de>>(des: D) -> Result<usize, D::Error> {
+    let id = String::deserialize(des)?;
+    archetype_index(&id).ok_or_else(|| D::Error::custom(format!("Unknown enemy type {:?}", id)))
+}
@@ -1,59 +1,246 @@
 use ggez::{Context, GameResult};
 
 use crate::{
-    util::angle_to_vec,
+    util::{angle_to_vec, angle_from_vec, Point2, Vector2},
     game::{
         DELTA,
-        world::Grid,
+        world::{Grid, Crossing},
+        sim::Event,
+        noise::{Noise, BREAK_NOISE},
     },
     io::tex::{Assets, Sprite}
 };
-use super::{Object, player::Player, enemy::Enemy, health::Health, weapon::Weapon};
+use super::{Object, circle_entry, player::Player, enemy::Enemy, health::Health, weapon::Weapon, decoration::{DecorationObj, first_hit}};
 
 #[derive(Debug, Clone)]
 pub struct Bullet<'a> {
     pub obj: Object,
     pub weapon: &'a Weapon,
+    pub owner: Owner,
+    /// Index of the enemy that fired it, the player's bullets have none
+    pub shooter: Option<usize>,
+    /// Damage left after going through walls
+    pub damage: f32,
+    /// Times the bullet has ricocheted
+    pub bounces: u8,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Who fired a bullet
+pub enum Owner {
+    Player,
+    Enemy,
+}
+
+impl Owner {
+    /// How much of its damage a shot or blast does to a target on a side
+    pub fn damage_scale(self, target: Owner, friendly_fire: FriendlyFire) -> f32 {
+        if self == target {
+            friendly_fire.damage_scale()
+        } else {
+            1.
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// What bullets do to the side that fired them, set per level
+pub enum FriendlyFire {
+    /// Bullets go through
+    Off,
+    /// Bullets only do part of their damage
+    Reduced,
+    Full,
+}
+
+impl Default for FriendlyFire {
+    #[inline]
+    fn default() -> Self {
+        FriendlyFire::Full
+    }
+}
+
+impl FriendlyFire {
+    /// How much of its damage a bullet does to a target of its own side
+    pub fn damage_scale(self) -> f32 {
+        match self {
+            FriendlyFire::Off => 0.,
+            FriendlyFire::Reduced => 0.35,
+            FriendlyFire::Full => 1.,
+        }
+    }
 }
 
 const SPEED: f32 = 1200.;
+/// Most times a bullet can ricochet
+const MAX_BOUNCES: u8 = 2;
+/// Sine of the steepest angle to a wall at which bullets still ricochet (about 25 degrees)
+const RICOCHET_SIN: f32 = 0.42;
+/// Fraction of its damage a bullet keeps when ricocheting
+const RICOCHET_DAMAGE: f32 = 0.5;
+/// Distance from a character's center at which bullets hit it
+pub const HIT_RADIUS: f32 = 16.;
+
+impl<'a> Bullet<'a> {
+    #[inline]
+    pub fn new(obj: Object, weapon: &'a Weapon, owner: Owner) -> Self {
+        Bullet {
+            obj,
+            weapon,
+            owner,
+            shooter: None,
+            damage: weapon.damage,
+            bounces: 0,
+        }
+    }
+}
 
 impl Bullet<'_> {
+    /// Keeps the index of the shooter right after an enemy has been removed
+    pub fn enemy_removed(&mut self, e: usize) {
+        self.shooter = match self.shooter {
+            Some(s) if s == e => None,
+            Some(s) if s > e => Some(s - 1),
+            s => s,
+        };
+    }
     #[inline]
-    pub fn apply_damage(&self, health: &mut Health) {
-        health.weapon_damage(self.weapon.damage, self.weapon.penetration)
+    pub fn apply_damage(&self, health: &mut Health, scale: f32) {
+        health.weapon_damage(scale * self.damage, self.weapon.penetration)
     }
     #[inline]
     pub fn draw(&self, ctx: &mut Context, a: &Assets) -> GameResult<()> {
         self.obj.draw(ctx, a.get_img(Sprite::Bullet))
     }
-    pub fn update(&mut self, grid: &Grid, player: &mut Player, enemies: &mut [Enemy]) -> Hit {
+    /// Moves the bullet and applies the damage of the first thing it hits on the way
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(&mut self, grid: &mut Grid, decorations: &[DecorationObj], player: &mut Player, enemies: &mut [Enemy], friendly_fire: FriendlyFire, events: &mut Vec<Event>) -> Hit {
         let start = self.obj.pos;
         let d_pos = SPEED * DELTA * angle_to_vec(self.obj.rot);
 
-        if Grid::dist_line_circle(start, d_pos, player.obj.pos) <= 16. {
-            self.apply_damage(&mut player.health);
-            return Hit::Player;
+        let penetration = self.weapon.material_penetration;
+        let mut stopped_at: Option<Crossing> = None;
+        // Solid tiles the bullet goes through and where it leaves them
+        let mut penetrated: Vec<(Crossing, Option<Point2>)> = Vec::new();
+        let cast = grid.cross(start, d_pos, true, |crossing| {
+            if let Some(last) = penetrated.last_mut() {
+                if last.1.is_none() {
+                    last.1 = Some(crossing.entry);
+                }
+            }
+            if !crossing.mat.solid() {
+                false
+            } else if penetration > crossing.mat.density() {
+                penetrated.push((crossing, None));
+                false
+            } else {
+                stopped_at = Some(crossing);
+                true
+            }
+        });
+
+        // Find whatever is hit first along the way
+        let len = d_pos.norm();
+        let mut reach = (cast.into_point() - start).norm();
+        let mut hit = match stopped_at {
+            Some(Crossing{tile: (x, y), ..}) => Hit::Wall(x, y),
+            None => Hit::None,
+        };
+        let mut closer = |t: f32, target: Hit| {
+            if t * len <= reach {
+                reach = t * len;
+                hit = target;
+            }
+        };
+        if let Some((i, t)) = first_hit(decorations, start, d_pos) {
+            closer(t, Hit::Decoration(i));
+        }
+        // Bullets go through their own side if there's no friendly fire,
+        // and through whoever fired them unless they glanced back off a wall
+        let fresh = self.bounces == 0;
+        let hits_player = self.owner.damage_scale(Owner::Player, friendly_fire) > 0. && !(fresh && self.owner == Owner::Player);
+        let hits_enemies = self.owner.damage_scale(Owner::Enemy, friendly_fire) > 0.;
+        let shooter = self.shooter.filter(|_| fresh);
+        if let Some(t) = sweep(player, start, d_pos).filter(|_| hits_player) {
+            closer(t, Hit::Player);
         }
-        for (i, enem) in enemies.into_iter().enumerate() {
-            if Grid::dist_line_circle(start, d_pos, enem.pl.obj.pos) <= 16. {
-                self.apply_damage(&mut enem.pl.health);
-                return Hit::Enemy(i);
+        for (i, enemy) in enemies.iter().enumerate().filter(|&(i, _)| hits_enemies && shooter != Some(i)) {
+            if let Some(t) = sweep(&enemy.pl, start, d_pos) {
+                closer(t, Hit::Enemy(i));
             }
         }
-        let cast = grid.ray_cast(start, d_pos, true);
-        self.obj.pos = cast.into_point();
-        if cast.full() {
-            Hit::None
-        } else {
-            Hit::Wall
+        let end = if len > 0. { start + reach / len * d_pos } else { start };
+        self.obj.pos = end;
+
+        let dir = angle_to_vec(self.obj.rot);
+        let nudge = Vector2::new(5.*dir.x.signum(), 5.*dir.y.signum());
+        for (crossing, exit) in penetrated {
+            if (crossing.entry - start).norm() >= reach {
+                break
+            }
+            // Tiles the bullet was already inside of were dealt with last tick
+            if crossing.normal.norm_squared() > 0. {
+                events.push(Event::Impact(Object::with_rot(crossing.entry + nudge, self.obj.rot)));
+                let (x, y) = crossing.tile;
+                if grid.damage(x, y, self.damage).is_some() {
+                    events.push(Event::Broken(crossing.entry));
+                    events.push(Event::Noise(Noise::new(crossing.entry, BREAK_NOISE)));
+                }
+                self.damage *= 1. - crossing.mat.density();
+            }
+            if let Some(exit) = exit.filter(|&exit| (exit - start).norm() <= reach) {
+                events.push(Event::Impact(Object::with_rot(exit - nudge, self.obj.rot)));
+            }
+        }
+
+        match hit {
+            Hit::Player => self.apply_damage(&mut player.health, self.owner.damage_scale(Owner::Player, friendly_fire)),
+            Hit::Enemy(i) => self.apply_damage(&mut enemies[i].pl.health, self.owner.damage_scale(Owner::Enemy, friendly_fire)),
+            Hit::Wall(x, y) => {
+                if let Some(crossing) = stopped_at {
+                    if self.ricochet(crossing) {
+                        events.push(Event::Impact(Object::with_rot(end + nudge, self.obj.rot)));
+                        hit = Hit::Ricochet(x, y);
+                    }
+                }
+            }
+            _ => (),
+        }
+        hit
+    }
+    /// Glances off the face of a tile if the angle is shallow enough
+    fn ricochet(&mut self, crossing: Crossing) -> bool {
+        let normal = crossing.normal;
+        let dir = angle_to_vec(self.obj.rot);
+        let steepness = -dir.dot(&normal);
+        // A zero normal means the bullet started inside the tile
+        let glances = steepness > 0. && steepness <= RICOCHET_SIN;
+        if !glances || !self.weapon.ricochets || !crossing.mat.ricochets() || self.bounces >= MAX_BOUNCES {
+            return false;
         }
+        self.obj.rot = angle_from_vec(dir + 2. * steepness * normal);
+        self.obj.pos = crossing.entry + 0.5 * normal;
+        self.damage *= RICOCHET_DAMAGE;
+        self.bounces += 1;
+        true
     }
 }
 
+/// How far along a line a bullet enters a character, from `0` to `1`
+///
+/// The character is assumed to keep moving like it did during the last tick.
+/// Bullets that start inside a character hit it right away.
+fn sweep(target: &Player, start: Point2, dist: Vector2) -> Option<f32> {
+    circle_entry(target.obj.pos, HIT_RADIUS, start, dist - target.moved)
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Hit {
-    Wall,
+    /// A solid tile was hit
+    Wall(u16, u16),
+    /// The bullet glanced off a solid tile and keeps flying
+    Ricochet(u16, u16),
+    Decoration(usize),
     Player,
     Enemy(usize),
     None,
@@ -1,49 +1,213 @@
 use crate::{
+    util::{Point2, Vector2},
+    game::world::Grid,
     io::tex::{Assets, Sprite},
 };
 use ggez::{Context, GameResult};
+use serde::{Serialize, Deserialize, Serializer, Deserializer, de::Error};
 
-use super::Object;
+use super::{Object, circle_entry, push_out_of_circle, push_out_of_rect};
+
+#[derive(Debug, Copy, Clone)]
+/// What a decoration collides as, centered on it and turned with it
+pub enum Shape {
+    /// Can be walked, shot and seen through
+    Open,
+    Circle {
+        radius: f32,
+    },
+    Rect {
+        half_width: f32,
+        half_height: f32,
+    },
+}
+
+impl Shape {
+    #[inline]
+    pub fn is_solid(self) -> bool {
+        match self {
+            Shape::Open => false,
+            _ => true,
+        }
+    }
+    /// Radius of a circle around the shape
+    pub fn bounding_radius(self) -> f32 {
+        match self {
+            Shape::Open => 0.,
+            Shape::Circle{radius} => radius,
+            Shape::Rect{half_width, half_height} => half_width.hypot(half_height),
+        }
+    }
+    /// Moves a circle so it doesn't overlap the shape placed at an object
+    pub fn push_out(self, obj: &Object, pos: &mut Point2, r: f32) {
+        match self {
+            Shape::Open => (),
+            Shape::Circle{radius} => push_out_of_circle(pos, r, obj.pos, radius),
+            Shape::Rect{half_width, half_height} => {
+                let mut local = to_local(obj, *pos);
+                push_out_of_rect(&mut local, r, Point2::new(-half_width, -half_height), Point2::new(half_width, half_height));
+                *pos = obj.pos + rotate(Vector2::new(local.x, local.y), obj.rot);
+            }
+        }
+    }
+    /// How far along a line it first touches the shape placed at an object, from `0` to `1`
+    pub fn ray_cast(self, obj: &Object, start: Point2, dist: Vector2) -> Option<f32> {
+        match self {
+            Shape::Open => None,
+            Shape::Circle{radius} => circle_entry(obj.pos, radius, start, dist),
+            Shape::Rect{half_width, half_height} => {
+                let start = to_local(obj, start);
+                let dist = rotate(dist, -obj.rot);
+
+                let mut enter = 0f32;
+                let mut exit = 1f32;
+                for &(s, d, half) in &[(start.x, dist.x, half_width), (start.y, dist.y, half_height)] {
+                    if d.abs() <= std::f32::EPSILON {
+                        if s < -half || s > half {
+                            return None;
+                        }
+                    } else {
+                        let t1 = (-half - s) / d;
+                        let t2 = (half - s) / d;
+                        enter = enter.max(t1.min(t2));
+                        exit = exit.min(t1.max(t2));
+                        if enter > exit {
+                            return None;
+                        }
+                    }
+                }
+                Some(enter)
+            }
+        }
+    }
+    /// Direction pointing out of the shape placed at an object, at a position on its edge
+    pub fn normal(self, obj: &Object, pos: Point2) -> Vector2 {
+        match self {
+            Shape::Open => Vector2::new(0., 0.),
+            Shape::Circle{..} => (pos - obj.pos).try_normalize(std::f32::EPSILON).unwrap_or_else(|| Vector2::new(0., 0.)),
+            Shape::Rect{half_width, half_height} => {
+                let local = to_local(obj, pos);
+                let normal = if (local.x / half_width).abs() >= (local.y / half_height).abs() {
+                    Vector2::new(local.x.signum(), 0.)
+                } else {
+                    Vector2::new(0., local.y.signum())
+                };
+                rotate(normal, obj.rot)
+            }
+        }
+    }
+}
+
+/// A position relative to an object, unturned by its rotation
+fn to_local(obj: &Object, pos: Point2) -> Point2 {
+    let v = rotate(pos - obj.pos, -obj.rot);
+    Point2::new(v.x, v.y)
+}
+fn rotate(v: Vector2, angle: f32) -> Vector2 {
+    let (sin, cos) = angle.sin_cos();
+    Vector2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
 
 #[derive(Debug, Copy, Clone)]
 pub struct DecorationDecl {
+    /// Identifier used to refer to the decoration in level files
+    pub id: &'static str,
     pub spr: Sprite,
-    pub solid: bool,
+    pub shape: Shape,
+    /// Damage it takes to break the decoration into debris, if it can be broken
+    pub hp: Option<f32>,
 }
 
-const fn decl(spr: Sprite, solid: bool) -> DecorationDecl {
-    DecorationDecl { spr, solid }
+const fn decl(id: &'static str, spr: Sprite, shape: Shape) -> DecorationDecl {
+    DecorationDecl { id, spr, shape, hp: None }
+}
+const fn breakable(id: &'static str, spr: Sprite, shape: Shape, hp: f32) -> DecorationDecl {
+    DecorationDecl { id, spr, shape, hp: Some(hp) }
+}
+
+const OPEN: Shape = Shape::Open;
+const fn circle(radius: f32) -> Shape {
+    Shape::Circle{radius}
+}
+const fn rect(half_width: f32, half_height: f32) -> Shape {
+    Shape::Rect{half_width, half_height}
 }
 
 pub const DECORATIONS: &[DecorationDecl] = &[
-    decl(Sprite::Chair1, false),
-    decl(Sprite::Chair2, false),
-    decl(Sprite::ChairBoss, false),
-    decl(Sprite::LampPost, false),
-    decl(Sprite::OfficePlant, false),
-    decl(Sprite::OfficePlant2, false),
-    decl(Sprite::OfficePlant3, false),
-    decl(Sprite::Trashcan, true),
-    decl(Sprite::ManholeCover, false),
-    decl(Sprite::ManholeCover2, false),
-    decl(Sprite::DeskLamp, false),
-    decl(Sprite::WallLight, false),
-    decl(Sprite::WallLight2, false),
-    decl(Sprite::WallLight3, false),
-    decl(Sprite::RoadMark, false),
+    decl("chair1", Sprite::Chair1, OPEN),
+    decl("chair2", Sprite::Chair2, OPEN),
+    decl("chair_boss", Sprite::ChairBoss, OPEN),
+    decl("lamp_post", Sprite::LampPost, circle(5.)),
+    breakable("officeplant", Sprite::OfficePlant, circle(9.), 40.),
+    breakable("officeplant2", Sprite::OfficePlant2, circle(9.), 40.),
+    breakable("officeplant3", Sprite::OfficePlant3, circle(9.), 40.),
+    breakable("trashcan", Sprite::Trashcan, circle(11.), 80.),
+    decl("manhole_cover", Sprite::ManholeCover, OPEN),
+    decl("manhole_cover2", Sprite::ManholeCover2, OPEN),
+    decl("desk_lamp", Sprite::DeskLamp, OPEN),
+    decl("wall_light", Sprite::WallLight, OPEN),
+    decl("wall_light2", Sprite::WallLight2, OPEN),
+    decl("wall_light3", Sprite::WallLight3, OPEN),
+    decl("road_mark", Sprite::RoadMark, OPEN),
+    decl("machinery1", Sprite::Machinery1, rect(15., 15.)),
+    decl("machinery2", Sprite::Machinery2, rect(15., 15.)),
+    decl("machinery3", Sprite::Machinery3, rect(15., 15.)),
+    decl("machinery4", Sprite::Machinery4, rect(15., 15.)),
+    decl("debris", Sprite::Debris, OPEN),
 ];
 
+lazy_static! {
+    /// Index in `DECORATIONS` of what broken decorations turn into
+    static ref DEBRIS: usize = decoration_index("debris").expect("debris isn't declared");
+}
+
+/// Looks up the index in `DECORATIONS` of a decoration by its identifier
+pub fn decoration_index(id: &str) -> Option<usize> {
+    DECORATIONS.iter().position(|d| d.id == id)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DecorationObj {
     pub obj: Object,
+    #[serde(rename = "id", serialize_with = "decl_ser", deserialize_with = "decl_des")]
     pub decl: usize,
+    #[serde(skip)]
+    /// Damage taken so far
+    pub damage_taken: f32,
+}
+
+/// Serialize the index of a decoration as its identifier
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn decl_ser<S: Serializer>(decl: &usize, ser: S) -> Result<S::Ok, S::Error> {
+    DECORATIONS[*decl].id.serialize(ser)
+}
+/// Deserialize the identifier of a decoration into its index
+fn decl_des<'de, D: Deserializer<'de>>(des: D) -> Result<usize, D::Error> {
+    let id = String::deserialize(des)?;
+    decoration_index(&id).ok_or_else(|| D::Error::custom(format!("Unknown decoration {:?}", id)))
 }
 
 impl DecorationObj {
     #[inline]
     pub fn new(obj: Object, decl: usize) -> Self {
         DecorationObj {
-            obj, decl
+            obj, decl,
+            damage_taken: 0.,
+        }
+    }
+    /// Damages the decoration and turns it into debris if it breaks, returning whether it did
+    pub fn damage(&mut self, dmg: f32) -> bool {
+        let hp = match DECORATIONS[self.decl].hp {
+            Some(hp) => hp,
+            None => return false,
+        };
+        self.damage_taken += dmg;
+        if self.damage_taken >= hp {
+            self.decl = *DEBRIS;
+            self.damage_taken = 0.;
+            true
+        } else {
+            false
         }
     }
     #[inline]
@@ -51,7 +215,28 @@ impl DecorationObj {
         self.obj.draw(ctx, a.get_img(DECORATIONS[self.decl].spr))
     }
     #[inline]
+    pub fn shape(&self) -> Shape {
+        DECORATIONS[self.decl].shape
+    }
+    #[inline]
     pub fn is_solid(&self) -> bool {
-        DECORATIONS[self.decl].solid
+        self.shape().is_solid()
+    }
+    #[inline]
+    pub fn push_out(&self, pos: &mut Point2, r: f32) {
+        self.shape().push_out(&self.obj, pos, r)
     }
+}
+
+/// Whether neither walls nor solid decorations are in the way of a line
+pub fn line_of_sight(grid: &Grid, decorations: &[DecorationObj], start: Point2, dist: Vector2) -> bool {
+    grid.ray_cast(start, dist, true).full() && first_hit(decorations, start, dist).is_none()
+}
+
+/// The first solid decoration a line runs into and how far along the line, from `0` to `1`
+pub fn first_hit(decorations: &[DecorationObj], start: Point2, dist: Vector2) -> Option<(usize, f32)> {
+    decorations.iter()
+        .enumerate()
+        .filter_map(|(i, d)| d.shape().ray_cast(&d.obj, start, dist).map(|t| (i, t)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
 }
\ No newline at end of file
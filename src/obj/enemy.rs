@@ -6,14 +6,22 @@ use ggez::{
 
 use crate::{
     util::{angle_from_vec, angle_to_vec},
-    io::{
-        snd::MediaPlayer,
-        tex::{Assets, Sprite},
-    },
-    game::{DELTA, world::Grid},
+    io::tex::Assets,
+    game::{DELTA, world::Grid, path::{Path, WalkMap}, cover::{CoverSpot, find_cover}, sim::Event},
+};
+
+use super::{
+    Object,
+    player::Player,
+    bullet::Owner,
+    weapon::Projectiles,
+    melee::in_reach,
+    decoration::{DecorationObj, line_of_sight},
+    skill::Skill,
+    archetype::{Archetype, ARCHETYPES, archetype_ser, archetype_des},
 };
 
-use super::{Object, player::Player};
+use rand::Rng;
 
 #[derive(Debug, Clone)]
 pub enum Chaser {
@@ -24,13 +32,27 @@ pub enum Chaser {
     },
     LookAround {
         dir: Vector2,
-    }
+    },
+    /// Heard something and goes to check it out
+    Investigating {
+        pos: Point2,
+    },
+    /// Hiding from the player while reloading or hurt
+    TakingCover {
+        spot: CoverSpot,
+        /// Last known position of the player
+        threat: Point2,
+        vel: Vector2,
+        /// Time spent peeking out if peeking
+        peeking: Option<f32>,
+    },
 }
 
 impl Chaser {
+    /// Whether the enemy knows about the player
     pub fn chasing(&self) -> bool {
         match *self {
-            Chaser::LastKnown{..} => true,
+            Chaser::LastKnown{..} | Chaser::TakingCover{..} => true,
             _ => false,
         }
     }
@@ -42,32 +64,136 @@ impl Default for Chaser {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A point on a patrol route
+pub struct Waypoint {
+    #[serde(serialize_with = "crate::io::save::point_ser", deserialize_with = "crate::io::save::point_des")]
+    pub pos: Point2,
+    /// Time spent at the waypoint before walking on
+    pub wait: f32,
+    /// Direction faced while waiting
+    pub rot: Option<f32>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Enemy {
     pub pl: Player,
+    #[serde(default, rename = "type", serialize_with = "archetype_ser", deserialize_with = "archetype_des")]
+    /// Index of the enemy's type in `ARCHETYPES`
+    pub archetype: usize,
+    #[serde(default)]
+    /// Waypoints walked in a loop when not alerted
+    pub patrol: Vec<Waypoint>,
+    #[serde(default)]
+    pub skill: Skill,
     #[serde(skip)]
     pub behaviour: Chaser,
+    #[serde(skip)]
+    /// The route taken while walking somewhere
+    pub path: Option<Path>,
+    #[serde(skip)]
+    /// Index of the patrol waypoint currently walked to
+    pub patrol_index: usize,
+    #[serde(skip)]
+    /// Time waited at the current patrol waypoint if arrived
+    pub waited: Option<f32>,
+    #[serde(skip)]
+    pub aim: Aim,
+    #[serde(skip)]
+    /// Time the enemy hasn't been able to get closer to its next waypoint
+    pub stuck: f32,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+/// State of an enemy's shooting
+pub struct Aim {
+    /// Time the player has been in sight
+    pub sighted: f32,
+    /// Shots fired in the current burst
+    pub burst: u16,
+    /// Time left until the next burst
+    pub pause: f32,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// How enemies that spot the player alert the others, set per level
+pub struct AlertSettings {
+    /// How far a call out can be heard
+    pub radius: f32,
+    /// Time it takes others to react to a call out
+    pub delay: f32,
+    /// Whether only enemies the caller can see react
+    pub line_of_sight: bool,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        AlertSettings {
+            radius: 320.,
+            delay: 0.5,
+            line_of_sight: true,
+        }
+    }
 }
 
-pub const VISIBILITY: f32 = ::std::f32::consts::FRAC_PI_4;
+/// Time an enemy tries to get past something blocking it before moving on
+const STUCK_TIME: f32 = 0.5;
+/// Time spent peeking out of cover without seeing the player before chasing them
+const PEEK_TIME: f32 = 2.;
 
 impl Enemy {
+    /// Makes a guard
+    #[inline]
     pub fn new(obj: Object) -> Enemy {
+        Enemy::of_type(obj, 0)
+    }
+    pub fn of_type(obj: Object, archetype: usize) -> Enemy {
+        let arch = &ARCHETYPES[archetype];
         Enemy {
-            pl: Player::new(obj),
+            pl: Player::new(obj).with_health(arch.health),
+            archetype,
+            patrol: Vec::new(),
+            skill: arch.skill,
             behaviour: Chaser::NoIntel,
+            path: None,
+            patrol_index: 0,
+            waited: None,
+            aim: Aim::default(),
+            stuck: 0.,
         }
     }
     pub fn draw_visibility_cone(&self, ctx: &mut Context, length: f32) -> GameResult<()> {
         let Object{pos, rot} = self.pl.obj;
-        let dir1 = angle_to_vec(rot - VISIBILITY);
-        let dir2 = angle_to_vec(rot + VISIBILITY);
+        let vision = self.archetype().vision;
+        let dir1 = angle_to_vec(rot - vision);
+        let dir2 = angle_to_vec(rot + vision);
         graphics::line(ctx, &[pos, pos + (length * dir1)], 1.5)?;
         graphics::line(ctx, &[pos, pos + (length * dir2)], 1.5)
     }
+    /// Draws the patrol route as a loop starting at the enemy
+    pub fn draw_patrol(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut from = self.pl.obj.pos;
+        for waypoint in &self.patrol {
+            if waypoint.pos != from {
+                graphics::line(ctx, &[from, waypoint.pos], 1.5)?;
+            }
+            graphics::circle(ctx, graphics::DrawMode::Fill, waypoint.pos, 4., 0.5)?;
+            if let Some(rot) = waypoint.rot {
+                graphics::line(ctx, &[waypoint.pos, waypoint.pos + 12. * angle_to_vec(rot)], 1.5)?;
+            }
+            from = waypoint.pos;
+        }
+        if let Some(first) = self.patrol.first() {
+            if self.patrol.len() > 1 && first.pos != from {
+                graphics::line(ctx, &[from, first.pos], 1.5)?;
+            }
+        }
+        Ok(())
+    }
     #[inline]
     pub fn draw(&self, ctx: &mut Context, a: &Assets) -> GameResult<()> {
-        self.pl.draw(ctx, a, Sprite::Enemy)
+        self.pl.draw(ctx, a, self.archetype().spr)
     }
     fn look_towards(&mut self, dist: Vector2) -> bool{
         let dir = angle_to_vec(self.pl.obj.rot);
@@ -88,29 +214,39 @@ impl Enemy {
             true
         }
     }
-    pub fn update(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<()> {
+    /// `others` are the positions of all the other characters
+    pub fn update(&mut self, grid: &Grid, decorations: &[DecorationObj], walkable: &WalkMap, others: &[Point2], events: &mut Vec<Event>) {
+        if self.aim.pause > DELTA {
+            self.aim.pause -= DELTA;
+        } else {
+            self.aim.pause = 0.;
+        }
+        self.pl.update(events);
         if let Some(wep) = &mut self.pl.wep {
-            wep.update(ctx, mplayer)?;
             if wep.cur_clip == 0 && wep.loading_time == 0. {
-                wep.reload(ctx, mplayer)?;
+                wep.reload(events);
             }
         }
         match self.behaviour {
-            Chaser::NoIntel => (),
+            Chaser::NoIntel => self.patrol(grid, decorations, walkable, others),
             Chaser::LastKnown{
                 pos: player_pos,
                 vel
             } => {
-                let dist = player_pos-self.pl.obj.pos;
-                self.look_towards(dist);
-
-                let distance = dist.norm();
-                const CHASE_SPEED: f32 = 100. * DELTA;
-
-                if distance >= CHASE_SPEED {
-                    let displace = CHASE_SPEED * dist / distance;
-                    self.pl.obj.pos += displace;
-                } else {
+                if self.should_hide() {
+                    if let Some(spot) = find_cover(grid, decorations, walkable, self.pl.obj.pos, player_pos) {
+                        self.path = None;
+                        self.behaviour = Chaser::TakingCover{spot, threat: player_pos, vel, peeking: None};
+                        return
+                    }
+                }
+                if self.archetype().holds_position {
+                    self.look_at(player_pos);
+                    return
+                }
+                // Look around once arrived or if there is no way there
+                if self.walk_to(player_pos, self.chase_speed(), grid, decorations, walkable, others).unwrap_or(true) {
+                    self.path = None;
                     self.behaviour = Chaser::LookAround{dir: vel};
                 }
             }
@@ -119,13 +255,233 @@ impl Enemy {
                     self.behaviour = Chaser::NoIntel;
                 }
             }
+            Chaser::Investigating{pos} => {
+                const INVESTIGATE_SPEED: f32 = 80. * DELTA;
+
+                if self.walk_to(pos, INVESTIGATE_SPEED, grid, decorations, walkable, others).unwrap_or(true) {
+                    // Turn around to see if anything is behind
+                    self.path = None;
+                    self.behaviour = Chaser::LookAround{dir: -angle_to_vec(self.pl.obj.rot)};
+                }
+            }
+            Chaser::TakingCover{threat, vel, ..} if self.out_of_ammo() => {
+                // Nothing left to shoot, so go after the player to strike them
+                self.path = None;
+                self.behaviour = Chaser::LastKnown{pos: threat, vel};
+            }
+            Chaser::TakingCover{spot, threat, vel, peeking: None} => {
+                if self.walk_to(spot.cover, self.chase_speed(), grid, decorations, walkable, others).unwrap_or(true) {
+                    self.look_at(threat);
+                    if self.weapon_ready() {
+                        let peeking = spot.peek.map(|_| 0.);
+                        if peeking.is_some() || self.pl.health.hp >= self.archetype().cover_health {
+                            self.path = None;
+                            self.behaviour = if peeking.is_some() {
+                                Chaser::TakingCover{spot, threat, vel, peeking}
+                            } else {
+                                // Nowhere to shoot from, so go after the player again
+                                Chaser::LastKnown{pos: threat, vel}
+                            };
+                        }
+                    }
+                }
+            }
+            Chaser::TakingCover{spot, threat, vel, peeking: Some(time)} => {
+                if !self.weapon_ready() {
+                    self.path = None;
+                    self.behaviour = Chaser::TakingCover{spot, threat, vel, peeking: None};
+                } else if time >= PEEK_TIME {
+                    self.path = None;
+                    self.behaviour = Chaser::LastKnown{pos: threat, vel};
+                } else {
+                    let peek = spot.peek.unwrap_or(spot.cover);
+                    if self.walk_to(peek, self.chase_speed(), grid, decorations, walkable, others).unwrap_or(true) {
+                        self.look_at(threat);
+                    }
+                    self.behaviour = Chaser::TakingCover{spot, threat, vel, peeking: Some(time + DELTA)};
+                }
+            }
+        }
+    }
+    /// The enemy sees the player
+    pub fn spot(&mut self, pos: Point2, vel: Vector2) {
+        self.behaviour = match self.behaviour {
+            // Keep shooting from cover
+            Chaser::TakingCover{spot, peeking, ..} => Chaser::TakingCover{
+                spot,
+                threat: pos,
+                vel,
+                peeking: peeking.map(|_| 0.),
+            },
+            _ => Chaser::LastKnown{pos, vel},
+        };
+    }
+    /// Fire at the player in sight if the enemy has reacted and isn't between bursts
+    ///
+    /// Returns where the shot was fired from along with its bullets.
+    pub fn shoot<R: Rng>(&mut self, rng: &mut R, events: &mut Vec<Event>) -> Option<(Object, Projectiles<'static>)> {
+        if self.aim.sighted < self.skill.reaction_time {
+            self.aim.sighted += DELTA;
+            return None;
+        }
+        if self.aim.pause > 0. {
+            return None;
+        }
+        let wep = self.pl.wep.as_mut()?;
+        let jerk = wep.jerk;
+        let bm = wep.shoot(events)?;
+
+        let pos = self.pl.obj.pos + wep.weapon.muzzle_distance() * angle_to_vec(self.pl.obj.rot);
+        let mut bul = Object::new(pos);
+        bul.rot = self.pl.obj.rot - self.skill.spray_control * jerk;
+        if self.skill.aim_error > 0. {
+            bul.rot += rng.gen_range(-self.skill.aim_error, self.skill.aim_error);
+        }
+
+        self.aim.burst += 1;
+        if self.skill.burst_length > 0 && self.aim.burst >= self.skill.burst_length {
+            self.aim.burst = 0;
+            self.aim.pause = self.skill.burst_pause;
+        }
+        Some((bul.clone(), bm.make(bul, Owner::Enemy)))
+    }
+    /// Strike the player in sight if they're in reach and the enemy has reacted
+    ///
+    /// Returns whether the enemy struck.
+    pub fn melee(&mut self, target: Point2, grid: &Grid) -> bool {
+        if self.aim.sighted < self.skill.reaction_time {
+            self.aim.sighted += DELTA;
+            return false;
+        }
+        if !self.pl.melee_ready() || !in_reach(&self.pl.obj, target, grid) {
+            return false;
+        }
+        self.pl.swing();
+        true
+    }
+    /// Whether the enemy has nothing left to shoot with
+    pub fn out_of_ammo(&self) -> bool {
+        self.pl.wep.map(|wep| wep.cur_clip == 0 && wep.ammo == 0 && !wep.reloading).unwrap_or(true)
+    }
+    /// The player went out of sight, so the enemy will have to react again
+    #[inline]
+    pub fn lose_sight(&mut self) {
+        self.aim.sighted = 0.;
+        self.aim.burst = 0;
+    }
+    /// Whether the enemy would rather be in cover
+    fn should_hide(&self) -> bool {
+        let reloading = self.pl.wep.map(|wep| wep.reloading).unwrap_or(false);
+        // Cover is no use without anything to shoot from it
+        !self.archetype().holds_position && !self.out_of_ammo() && (reloading || self.pl.health.hp < self.archetype().cover_health)
+    }
+    #[inline]
+    pub fn archetype(&self) -> &'static Archetype {
+        &ARCHETYPES[self.archetype]
+    }
+    #[inline]
+    fn chase_speed(&self) -> f32 {
+        self.archetype().speed * DELTA
+    }
+    /// Whether the weapon can be fired right away
+    fn weapon_ready(&self) -> bool {
+        self.pl.wep.map(|wep| !wep.reloading && wep.cur_clip > 0).unwrap_or(false)
+    }
+    fn look_at(&mut self, p: Point2) {
+        let dist = p - self.pl.obj.pos;
+        if dist.norm() > 0. {
+            self.look_towards(dist);
         }
-        Ok(())
     }
-    pub fn can_see(&self, p: Point2, grid: &Grid) -> bool {
+    /// React to a noise made at a position
+    pub fn hear(&mut self, pos: Point2) {
+        // Chasing the player is more important
+        if self.behaviour.chasing() {
+            return
+        }
+        self.behaviour = if self.archetype().holds_position {
+            Chaser::LookAround{dir: pos - self.pl.obj.pos}
+        } else {
+            Chaser::Investigating{pos}
+        };
+    }
+    /// Follow the patrol route if there is one
+    fn patrol(&mut self, grid: &Grid, decorations: &[DecorationObj], walkable: &WalkMap, others: &[Point2]) {
+        let Waypoint{pos, wait, rot} = match self.patrol.get(self.patrol_index) {
+            Some(waypoint) => waypoint.clone(),
+            None => return,
+        };
+        const PATROL_SPEED: f32 = 60. * DELTA;
+
+        if let Some(waited) = self.waited {
+            if let Some(rot) = rot {
+                self.look_towards(angle_to_vec(rot));
+            }
+            if waited >= wait {
+                self.waited = None;
+                self.patrol_index = (self.patrol_index + 1) % self.patrol.len();
+            } else {
+                self.waited = Some(waited + DELTA);
+            }
+        } else if self.walk_to(pos, PATROL_SPEED, grid, decorations, walkable, others).unwrap_or(true) {
+            // Waypoints that can't be reached are waited at too, so the route goes on
+            self.path = None;
+            self.waited = Some(0.);
+        }
+    }
+    /// Take a step along a path to the goal
+    ///
+    /// Returns whether the goal has been reached or `None` if it can't be reached.
+    fn walk_to(&mut self, goal: Point2, speed: f32, grid: &Grid, decorations: &[DecorationObj], walkable: &WalkMap, others: &[Point2]) -> Option<bool> {
+        // Only find a new path once the goal has moved to another tile, like a player on the run
+        let same_tile = self.path.as_ref().map(|p| Grid::snap(p.goal()) == Grid::snap(goal)).unwrap_or(false);
+        if same_tile {
+            if let Some(path) = &mut self.path {
+                path.retarget(goal);
+            }
+        } else {
+            self.path = Path::find(grid, decorations, walkable, self.pl.obj.pos, goal);
+        }
+        let waypoint = self.path.as_ref()?.next();
+
+        if let Some(waypoint) = waypoint {
+            let dist = waypoint-self.pl.obj.pos;
+            let distance = dist.norm();
+            if distance > 0. {
+                self.look_towards(dist);
+            }
+
+            let start = self.pl.obj.pos;
+            let step = if distance >= speed {
+                speed * dist / distance
+            } else {
+                dist
+            };
+            self.pl.obj.move_character(step, grid, decorations, others);
+
+            // Give up on waypoints someone else is standing in the way of
+            if (self.pl.obj.pos - start).norm() < 0.25 * step.norm() {
+                self.stuck += DELTA;
+            } else {
+                self.stuck = 0.;
+            }
+            if distance < speed || self.stuck >= STUCK_TIME {
+                self.stuck = 0.;
+                if let Some(path) = &mut self.path {
+                    path.advance();
+                }
+            }
+        }
+        self.path.as_ref().map(Path::is_finished)
+    }
+    /// Whether a position is inside the vision cone, even if something is in the way
+    pub fn faces(&self, p: Point2) -> bool {
         let dist = p-self.pl.obj.pos;
         let dir = angle_to_vec(self.pl.obj.rot);
 
-        na::angle(&dir, &dist) <= VISIBILITY && grid.ray_cast(self.pl.obj.pos, dist, true).full()
+        na::angle(&dir, &dist) <= self.archetype().vision
+    }
+    pub fn can_see(&self, p: Point2, grid: &Grid, decorations: &[DecorationObj]) -> bool {
+        self.faces(p) && line_of_sight(grid, decorations, self.pl.obj.pos, p-self.pl.obj.pos)
     }
 }
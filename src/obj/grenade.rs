@@ -0,0 +1,179 @@
+use ggez::{Context, GameResult, graphics};
+
+use crate::{
+    util::{angle_to_vec, Point2, Vector2},
+    game::{
+        DELTA,
+        world::{Grid, Crossing},
+        sim::Event,
+    },
+    io::tex::Assets,
+};
+use super::{
+    Object,
+    bullet::Owner,
+    health::Health,
+    weapon::{Weapon, FireMode},
+    decoration::{DecorationObj, first_hit, line_of_sight},
+};
+
+/// Speed along the ground grenades are thrown with
+const THROW_SPEED: f32 = 380.;
+/// Upwards speed grenades are thrown with
+const THROW_CLIMB: f32 = 240.;
+const GRAVITY: f32 = 900.;
+/// Fraction of its upwards speed a grenade keeps when bouncing off the ground
+const GROUND_BOUNCE: f32 = 0.35;
+/// Fraction of its speed along the ground a grenade keeps when landing
+const GROUND_FRICTION: f32 = 0.7;
+/// Slowest a grenade can land with and still bounce back up
+const MIN_BOUNCE: f32 = 60.;
+/// Fraction of its speed a grenade keeps when bouncing off a wall
+const WALL_BOUNCE: f32 = 0.5;
+/// How quickly a rolling grenade slows down
+const ROLL_FRICTION: f32 = 2.5;
+
+#[derive(Debug, Clone)]
+/// A thrown explosive flying in an arc until its fuse runs out
+pub struct Grenade<'a> {
+    pub obj: Object,
+    /// Velocity along the ground
+    pub vel: Vector2,
+    /// Height above the ground
+    pub height: f32,
+    /// Upwards speed
+    pub climb: f32,
+    /// Time left until it explodes
+    pub fuse: f32,
+    pub weapon: &'a Weapon,
+    pub owner: Owner,
+}
+
+impl<'a> Grenade<'a> {
+    /// Throws a grenade from where a character stands in the direction it's facing
+    pub fn new(obj: Object, weapon: &'a Weapon, owner: Owner) -> Self {
+        let fuse = match weapon.fire_mode {
+            FireMode::Thrown{fuse, ..} => fuse,
+            _ => 0.,
+        };
+        Grenade {
+            vel: THROW_SPEED * angle_to_vec(obj.rot),
+            obj,
+            height: 0.,
+            climb: THROW_CLIMB,
+            fuse,
+            weapon,
+            owner,
+        }
+    }
+}
+
+impl Grenade<'_> {
+    #[inline]
+    pub fn blast_radius(&self) -> f32 {
+        match self.weapon.fire_mode {
+            FireMode::Thrown{blast_radius, ..} => blast_radius,
+            _ => 0.,
+        }
+    }
+    /// Moves the grenade and burns its fuse, returning whether it explodes
+    pub fn update(&mut self, grid: &Grid, decorations: &[DecorationObj], events: &mut Vec<Event>) -> bool {
+        let start = self.obj.pos;
+        let d_pos = DELTA * self.vel;
+        let mut wall: Option<Crossing> = None;
+        // The tile it starts in is ignored so it can't get stuck
+        let cast = grid.cross(start, d_pos, true, |crossing| {
+            if crossing.mat.solid() && crossing.normal.norm_squared() > 0. {
+                wall = Some(crossing);
+                true
+            } else {
+                false
+            }
+        });
+        let mut bounce = wall.map(|Crossing{entry, normal, ..}| (entry, normal));
+        // Solid decorations are bounced off like walls, unless it's already moving away from them
+        if let Some((i, t)) = first_hit(decorations, start, d_pos) {
+            let entry = start + t * d_pos;
+            let decoration = &decorations[i];
+            let normal = decoration.shape().normal(&decoration.obj, entry);
+            let closer = bounce.map_or(true, |(wall_entry, _)| t * d_pos.norm() < (wall_entry - start).norm());
+            if closer && self.vel.dot(&normal) < 0. {
+                bounce = Some((entry, normal));
+            }
+        }
+        match bounce {
+            Some((entry, normal)) => {
+                if self.vel.norm() >= MIN_BOUNCE {
+                    events.push(Event::Sound(self.weapon.impact_snd));
+                }
+                self.vel = WALL_BOUNCE * (self.vel - 2. * self.vel.dot(&normal) * normal);
+                self.obj.pos = entry + 0.5 * normal;
+            }
+            None => self.obj.pos = cast.into_point(),
+        }
+
+        if self.height > 0. || self.climb > 0. {
+            self.climb -= GRAVITY * DELTA;
+            self.height += self.climb * DELTA;
+            if self.height <= 0. {
+                self.height = 0.;
+                self.climb = if -self.climb >= MIN_BOUNCE {
+                    -GROUND_BOUNCE * self.climb
+                } else {
+                    0.
+                };
+                self.vel *= GROUND_FRICTION;
+            }
+        } else {
+            self.vel *= (1. - ROLL_FRICTION * DELTA).max(0.);
+        }
+        // Spin while moving
+        self.obj.rot += self.vel.norm() * DELTA / 8.;
+
+        self.fuse -= DELTA;
+        self.fuse <= 0.
+    }
+    /// Damage the explosion does at a position, if it reaches it
+    ///
+    /// Damage falls off with distance, walls and solid decorations block the blast completely.
+    pub fn blast(&self, grid: &Grid, decorations: &[DecorationObj], target: Point2) -> Option<f32> {
+        let dist = target - self.obj.pos;
+        if !line_of_sight(grid, decorations, self.obj.pos, dist) {
+            return None;
+        }
+        self.falloff(dist)
+    }
+    /// Damage the explosion does to a decoration, which only the others can shield
+    pub fn blast_decoration(&self, grid: &Grid, decorations: &[DecorationObj], i: usize) -> Option<f32> {
+        let dist = decorations[i].obj.pos - self.obj.pos;
+        let shielded = decorations.iter()
+            .enumerate()
+            .any(|(j, d)| j != i && d.shape().ray_cast(&d.obj, self.obj.pos, dist).is_some());
+        if shielded || !grid.ray_cast(self.obj.pos, dist, true).full() {
+            return None;
+        }
+        self.falloff(dist)
+    }
+    fn falloff(&self, dist: Vector2) -> Option<f32> {
+        let radius = self.blast_radius();
+        let d = dist.norm();
+        if d >= radius {
+            return None;
+        }
+        Some(self.weapon.damage * (1. - d / radius))
+    }
+    /// Hurts a character caught in the blast, the weapon's `penetration` is how much of it gets past armour
+    #[inline]
+    pub fn apply_damage(&self, health: &mut Health, dmg: f32) {
+        health.weapon_damage(dmg, self.weapon.penetration)
+    }
+    pub fn draw(&self, ctx: &mut Context, a: &Assets) -> GameResult<()> {
+        // Drawn bigger the higher up it is
+        let scale = 0.5 + self.height / 200.;
+        let drawparams = graphics::DrawParam {
+            scale: Point2::new(scale, scale),
+            .. self.obj.drawparams()
+        };
+        graphics::draw_ex(ctx, a.get_img(self.weapon.entity_sprite), drawparams)
+    }
+}
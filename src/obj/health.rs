@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Health {
     pub hp: f32,
     pub armour: f32,
@@ -17,6 +17,11 @@ impl Health {
             self.armour = 0.;
         }
     }
+    /// Takes all health at once, regardless of armour
+    #[inline]
+    pub fn kill(&mut self) {
+        self.hp = 0.;
+    }
     #[inline]
     pub fn is_dead(self) -> bool {
         self.hp <= 0.
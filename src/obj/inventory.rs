@@ -0,0 +1,58 @@
+//! The weapons the player carries
+use super::{weapon::WeaponInstance, ammo::{Calibre, CALIBRES}};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Where a weapon is carried, each slot holds one weapon
+pub enum Slot {
+    Primary,
+    Secondary,
+    Sidearm,
+    Throwable,
+}
+
+impl Default for Slot {
+    #[inline]
+    fn default() -> Self {
+        Slot::Primary
+    }
+}
+
+impl Slot {
+    /// All slots in the order they're switched through
+    pub const ALL: [Slot; 4] = [Slot::Primary, Slot::Secondary, Slot::Sidearm, Slot::Throwable];
+
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Time it takes to draw another weapon
+pub const SWITCH_TIME: f32 = 0.4;
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Inventory<'a> {
+    /// The weapons in their slots
+    ///
+    /// While the player holds a weapon it's taken out of its slot.
+    pub slots: [Option<WeaponInstance<'a>>; 4],
+    /// Slot of the weapon in hand
+    pub drawn: Slot,
+    /// Time left until the weapon in hand is ready
+    pub switching: f32,
+    /// Spare rounds of each calibre
+    ///
+    /// Rounds for the weapon in hand are kept with it instead, holstered weapons keep none.
+    pub ammo: [u16; CALIBRES],
+}
+
+impl<'a> Inventory<'a> {
+    #[inline]
+    pub fn get(&self, slot: Slot) -> Option<WeaponInstance<'a>> {
+        self.slots[slot.index()]
+    }
+    #[inline]
+    pub fn rounds(&self, calibre: Calibre) -> u16 {
+        self.ammo[calibre.index()]
+    }
+}
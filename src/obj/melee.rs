@@ -0,0 +1,30 @@
+//! Striking characters up close
+use std::f32::consts::PI;
+
+use ggez::nalgebra as na;
+
+use crate::{
+    util::{angle_to_vec, Point2},
+    game::world::Grid,
+};
+use super::Object;
+
+/// Distance from a character's center it can strike at
+pub const MELEE_REACH: f32 = 40.;
+/// Half the angle in front of a character it can strike in
+const MELEE_ARC: f32 = PI / 3.;
+pub const MELEE_DAMAGE: f32 = 50.;
+/// Fraction of armour damage redirected to hp damage
+pub const MELEE_PENETRATION: f32 = 0.5;
+/// Time between strikes
+pub const MELEE_COOLDOWN: f32 = 0.6;
+/// Time a strike is shown for
+pub const STRIKE_TIME: f32 = 0.15;
+
+/// Whether a character could strike something at a position
+pub fn in_reach(obj: &Object, target: Point2, grid: &Grid) -> bool {
+    let dist = target - obj.pos;
+    dist.norm() <= MELEE_REACH
+        && na::angle(&angle_to_vec(obj.rot), &dist) <= MELEE_ARC
+        && grid.ray_cast(obj.pos, dist, true).full()
+}
@@ -4,14 +4,26 @@ use ggez::graphics::{self, Point2, Vector2, Image};
 
 pub mod player;
 pub mod enemy;
+pub mod skill;
+pub mod archetype;
 pub mod health;
 pub mod weapon;
+pub mod ammo;
+pub mod inventory;
 pub mod bullet;
+pub mod grenade;
+pub mod melee;
 pub mod pickup;
 pub mod decoration;
 
 use crate::game::world::Grid;
-use crate::game::DELTA;
+
+use self::decoration::DecorationObj;
+
+/// Radius of the circle characters collide as
+pub const CHARACTER_RADIUS: f32 = 14.;
+/// How many times overlaps are resolved per move, as resolving one can cause another
+const RESOLVE_ITERATIONS: usize = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// A simple object that can be drawn to the screen
@@ -55,23 +67,107 @@ impl Object {
         let (x, y) = Grid::snap(self.pos);
         grid.is_solid(x, y)
     }
-    pub fn move_on_grid(&mut self, mut v: Vector2, speed: f32, grid: &Grid) {
-        if v.x != 0. {
-            let (xx, xy) = Grid::snap(self.pos + Vector2::new(16. * v.x, 0.));
-            if grid.is_solid(xx, xy) {
-                v.x = 0.;
+    /// Moves a character, sliding along solid tiles, solid decorations and other characters
+    ///
+    /// `others` are the positions of the other characters, not including this one.
+    pub fn move_character(&mut self, displace: Vector2, grid: &Grid, decorations: &[DecorationObj], others: &[Point2]) {
+        self.pos += displace;
+
+        for _ in 0..RESOLVE_ITERATIONS {
+            for &other in others {
+                push_out_of_circle(&mut self.pos, CHARACTER_RADIUS, other, CHARACTER_RADIUS);
             }
+            for decoration in decorations {
+                decoration.push_out(&mut self.pos, CHARACTER_RADIUS);
+            }
+            push_out_of_tiles(&mut self.pos, CHARACTER_RADIUS, grid);
         }
-        if v.y != 0. {
-            let (yx, yy) = Grid::snap(self.pos + Vector2::new(0., 16. * v.y));
-            if grid.is_solid(yx, yy) {
-                v.y = 0.;
+    }
+}
+
+/// Moves a circle so it doesn't overlap another circle
+fn push_out_of_circle(pos: &mut Point2, r: f32, other: Point2, other_r: f32) {
+    let dist = *pos - other;
+    let len = dist.norm();
+    let min = r + other_r;
+    if len >= min {
+        return
+    }
+    if len > 0. {
+        *pos += dist * ((min - len) / len);
+    } else {
+        pos.x += min;
+    }
+}
+
+/// How far along a line it first touches a circle, from `0` to `1`
+fn circle_entry(center: Point2, radius: f32, start: Point2, dist: Vector2) -> Option<f32> {
+    let f = start - center;
+    let a = dist.norm_squared();
+    let c = f.norm_squared() - radius * radius;
+    if c <= 0. {
+        return Some(0.);
+    }
+    if a <= std::f32::EPSILON {
+        return None;
+    }
+    let b = 2. * f.dot(&dist);
+    let discriminant = b * b - 4. * a * c;
+    if discriminant < 0. {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2. * a);
+    if t >= 0. && t <= 1. {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Moves a circle out of the solid tiles it overlaps
+fn push_out_of_tiles(pos: &mut Point2, r: f32, grid: &Grid) {
+    let min_x = ((pos.x - r) / 32.).floor() as i32;
+    let max_x = ((pos.x + r) / 32.).floor() as i32;
+    let min_y = ((pos.y - r) / 32.).floor() as i32;
+    let max_y = ((pos.y + r) / 32.).floor() as i32;
+
+    for x in min_x..=max_x {
+        for y in min_y..=max_y {
+            // Everything outside of the grid is solid
+            let solid = x < 0 || y < 0 || grid.is_solid(x as u16, y as u16);
+            if solid {
+                let corner = Point2::new(x as f32 * 32., y as f32 * 32.);
+                push_out_of_rect(pos, r, corner, corner + Vector2::new(32., 32.));
             }
         }
+    }
+}
+
+/// Moves a circle out of a rectangle
+fn push_out_of_rect(pos: &mut Point2, r: f32, min: Point2, max: Point2) {
+    let closest = Point2::new(pos.x.max(min.x).min(max.x), pos.y.max(min.y).min(max.y));
+    let dist = *pos - closest;
+    let len = dist.norm();
+    if len >= r {
+        return
+    }
+    if len > 0. {
+        *pos += dist * ((r - len) / len);
+    } else {
+        // The center is inside, so leave through the nearest side
+        let left = pos.x - min.x;
+        let right = max.x - pos.x;
+        let top = pos.y - min.y;
+        let bottom = max.y - pos.y;
 
-        if v.norm_squared() != 0. {
-            v = v.normalize();
+        if left <= right && left <= top && left <= bottom {
+            pos.x = min.x - r;
+        } else if right <= top && right <= bottom {
+            pos.x = max.x + r;
+        } else if top <= bottom {
+            pos.y = min.y - r;
+        } else {
+            pos.y = max.y + r;
         }
-        self.pos += v * speed * DELTA;
     }
 }
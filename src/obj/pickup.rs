@@ -9,7 +9,7 @@ use ggez::{
 
 use std::fmt::{self, Debug};
 
-use super::health::Health;
+use super::{health::Health, player::Player, ammo::Calibre};
 
 #[derive(Debug, Clone)]
 pub struct Pickup {
@@ -25,9 +25,11 @@ impl Pickup {
             pickup_type: &PICKUPS[i as usize]
         }
     }
-    #[inline]
-    pub fn apply(&self, health: &mut Health) {
-        (self.pickup_type.ability)(health)
+    pub fn apply(&self, pl: &mut Player) {
+        match self.pickup_type.ability {
+            Ability::Heal(heal) => heal(&mut pl.health),
+            Ability::Ammo(calibre, rounds) => pl.add_ammo(calibre, rounds),
+        }
     }
     #[inline]
     pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
@@ -35,10 +37,20 @@ impl Pickup {
     }
 }
 
+#[derive(Copy, Clone)]
+/// What a pickup does to whoever picks it up
+enum Ability {
+    Heal(fn(&mut Health)),
+    /// A box of rounds of a calibre
+    Ammo(Calibre, u16),
+}
+
 #[derive(Copy, Clone)]
 pub struct PickupType {
+    /// Identifier used to refer to the pickup in level files
+    pub id: &'static str,
     pub spr: Sprite,
-    ability: fn(&mut Health),
+    ability: Ability,
 }
 
 impl PickupType {
@@ -56,25 +68,69 @@ impl PickupType {
 impl Debug for PickupType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("PickupType")
+            .field("id", &self.id)
             .field("spr", &self.spr)
             .finish()
     }
 }
 
-pub const PICKUPS: [PickupType; 3] = [
+pub const PICKUPS: [PickupType; 10] = [
     PickupType {
+        id: "health_pack",
         spr: Sprite::HealthPack,
-        ability: health_pack
+        ability: Ability::Heal(health_pack),
     },
     PickupType {
+        id: "armour",
         spr: Sprite::Armour,
-        ability: armour
+        ability: Ability::Heal(armour),
     },
     PickupType {
+        id: "adrenaline",
         spr: Sprite::Adrenaline,
-        ability: adrenaline,
-    }
+        ability: Ability::Heal(adrenaline),
+    },
+    PickupType {
+        id: "ammo_9mm",
+        spr: Sprite::Ammo9mm,
+        ability: Ability::Ammo(Calibre::Mm9, 32),
+    },
+    PickupType {
+        id: "ammo_556",
+        spr: Sprite::Ammo556,
+        ability: Ability::Ammo(Calibre::Mm556, 60),
+    },
+    PickupType {
+        id: "ammo_762",
+        spr: Sprite::Ammo762,
+        ability: Ability::Ammo(Calibre::Mm762, 60),
+    },
+    PickupType {
+        id: "ammo_shells",
+        spr: Sprite::AmmoShells,
+        ability: Ability::Ammo(Calibre::Gauge12, 14),
+    },
+    PickupType {
+        id: "ammo_57",
+        spr: Sprite::Ammo57,
+        ability: Ability::Ammo(Calibre::Mm57, 40),
+    },
+    PickupType {
+        id: "ammo_50",
+        spr: Sprite::Ammo50,
+        ability: Ability::Ammo(Calibre::Cal50, 10),
+    },
+    PickupType {
+        id: "ammo_frag",
+        spr: Sprite::AmmoFrag,
+        ability: Ability::Ammo(Calibre::Frag, 2),
+    },
 ];
+/// Looks up the index in `PICKUPS` of a pickup by its identifier
+pub fn pickup_index(id: &str) -> Option<u8> {
+    PICKUPS.iter().position(|p| p.id == id).map(|i| i as u8)
+}
+
 fn health_pack(health: &mut Health) {
     health.hp = 100.;
 }
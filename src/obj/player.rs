@@ -1,31 +1,64 @@
+use std::f32::consts::PI;
+
 use ggez::{Context, GameResult, graphics};
 
 use crate::{
-    util::{Point2, angle_to_vec},
-    io::{
-        snd::MediaPlayer,
-        tex::{Assets, Sprite},
-    },
+    util::{Point2, Vector2, angle_to_vec},
+    io::tex::{Assets, Sprite},
+    game::{DELTA, sim::Event},
 };
 
-use super::{Object, health::Health, weapon::WeaponInstance};
+use super::{
+    Object,
+    health::Health,
+    weapon::WeaponInstance,
+    ammo::Calibre,
+    inventory::{Inventory, Slot, SWITCH_TIME},
+    melee::{MELEE_COOLDOWN, STRIKE_TIME},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
     pub obj: Object,
     #[serde(skip)]
+    /// The weapon in hand
     pub wep: Option<WeaponInstance<'static>>,
     #[serde(skip)]
+    /// The weapons carried but not held
+    pub inventory: Inventory<'static>,
+    #[serde(skip)]
     pub health: Health,
+    #[serde(skip, default = "no_movement")]
+    /// How far it moved during the last tick
+    pub moved: Vector2,
+    #[serde(skip)]
+    /// Time until it can strike again
+    pub melee_cooldown: f32,
+}
+
+#[inline]
+fn no_movement() -> Vector2 {
+    Vector2::new(0., 0.)
 }
 
+/// Where holstered weapons are drawn for each slot, as distance behind, distance to the side and turn
+const HOLSTERS: [(f32, f32, f32); 4] = [
+    (8., 4., 0.75 * PI),
+    (8., -4., -0.75 * PI),
+    (0., 13., 0.5 * PI),
+    (0., -13., -0.5 * PI),
+];
+
 impl Player {
     #[inline]
     pub fn new(obj: Object) -> Self {
         Self {
             obj,
             wep: None,
+            inventory: Inventory::default(),
             health: Health::default(),
+            moved: no_movement(),
+            melee_cooldown: 0.,
         }
     }
     #[inline]
@@ -46,6 +79,132 @@ impl Player {
             .. self
         }
     }
+    /// Carries the weapons of an inventory, holding the drawn one or else the first one there is
+    pub fn with_inventory(self, mut inventory: Inventory<'static>) -> Self {
+        if inventory.get(inventory.drawn).is_none() {
+            if let Some(&slot) = Slot::ALL.iter().find(|&&slot| inventory.get(slot).is_some()) {
+                inventory.drawn = slot;
+            }
+        }
+        let wep = inventory.slots[inventory.drawn.index()].take();
+        inventory.switching = 0.;
+        let mut player = Self {
+            wep,
+            inventory,
+            .. self
+        };
+        player.unstow_ammo();
+        player
+    }
+    /// Every weapon carried, with the one in hand put back in its slot
+    pub fn full_inventory(&self) -> Inventory<'static> {
+        let mut player = self.clone();
+        player.stow_ammo();
+        let mut inventory = player.inventory;
+        if let Some(wep) = player.wep {
+            inventory.slots[inventory.drawn.index()] = Some(wep);
+        }
+        inventory.switching = 0.;
+        inventory
+    }
+    /// Rounds carried of a calibre, counting those for the weapon in hand
+    pub fn rounds(&self, calibre: Calibre) -> u16 {
+        let held = self.wep
+            .filter(|wep| wep.weapon.calibre == calibre)
+            .map(|wep| wep.ammo)
+            .unwrap_or(0);
+        self.inventory.rounds(calibre) + held
+    }
+    /// Adds spare rounds of a calibre
+    pub fn add_ammo(&mut self, calibre: Calibre, rounds: u16) {
+        if let Some(wep) = self.wep.as_mut().filter(|wep| wep.weapon.calibre == calibre) {
+            wep.ammo = wep.ammo.saturating_add(rounds);
+        } else {
+            let ammo = &mut self.inventory.ammo[calibre.index()];
+            *ammo = ammo.saturating_add(rounds);
+        }
+    }
+    /// Whether any weapon carried takes a calibre
+    pub fn uses(&self, calibre: Calibre) -> bool {
+        self.wep.iter()
+            .chain(self.inventory.slots.iter().flatten())
+            .any(|wep| wep.weapon.calibre == calibre)
+    }
+    /// Puts the spare rounds of the weapon in hand back with the rest
+    fn stow_ammo(&mut self) {
+        if let Some(wep) = &mut self.wep {
+            let ammo = &mut self.inventory.ammo[wep.weapon.calibre.index()];
+            *ammo = ammo.saturating_add(wep.ammo);
+            wep.ammo = 0;
+        }
+    }
+    /// Gives the weapon in hand all spare rounds of its calibre
+    fn unstow_ammo(&mut self) {
+        if let Some(wep) = &mut self.wep {
+            let ammo = &mut self.inventory.ammo[wep.weapon.calibre.index()];
+            wep.ammo = wep.ammo.saturating_add(*ammo);
+            *ammo = 0;
+        }
+    }
+    /// Whether a weapon is carried in a slot, in hand or holstered
+    pub fn carries(&self, slot: Slot) -> bool {
+        self.inventory.get(slot).is_some() || (self.inventory.drawn == slot && self.wep.is_some())
+    }
+    /// Draws the weapon in a slot and holsters the one in hand
+    ///
+    /// Returns whether there was a weapon to draw.
+    pub fn switch_to(&mut self, slot: Slot) -> bool {
+        let wep = match self.inventory.slots[slot.index()].take() {
+            Some(wep) => wep,
+            None => return false,
+        };
+        self.stow_ammo();
+        if let Some(held) = self.wep.take() {
+            self.inventory.slots[self.inventory.drawn.index()] = Some(held);
+        }
+        self.wep = Some(wep);
+        self.unstow_ammo();
+        self.inventory.drawn = slot;
+        self.inventory.switching = SWITCH_TIME;
+        true
+    }
+    /// Draws the next weapon carried, or the previous one for a negative step
+    pub fn cycle_weapons(&mut self, step: i32) -> bool {
+        let n = Slot::ALL.len() as i32;
+        let cur = self.inventory.drawn.index() as i32;
+        for i in 1..n {
+            let next = ((cur + step.signum() * i) % n + n) % n;
+            if self.switch_to(Slot::ALL[next as usize]) {
+                return true;
+            }
+        }
+        false
+    }
+    /// Puts a weapon in its slot and draws it
+    ///
+    /// Its spare rounds go with the rest of the ammunition.
+    /// Returns the weapon that was in the slot before, without spare rounds.
+    pub fn pick_up(&mut self, mut wep: WeaponInstance<'static>) -> Option<WeaponInstance<'static>> {
+        let spare = std::mem::replace(&mut wep.ammo, 0);
+        self.add_ammo(wep.weapon.calibre, spare);
+
+        let slot = wep.weapon.slot;
+        if self.inventory.drawn == slot {
+            self.inventory.switching = SWITCH_TIME;
+            self.stow_ammo();
+            let old = std::mem::replace(&mut self.wep, Some(wep));
+            self.unstow_ammo();
+            return old;
+        }
+        let old = std::mem::replace(&mut self.inventory.slots[slot.index()], Some(wep));
+        self.switch_to(slot);
+        old
+    }
+    /// Whether the weapon in hand is still being drawn
+    #[inline]
+    pub fn is_switching(&self) -> bool {
+        self.inventory.switching > 0.
+    }
 
     /// Draw the object
     // pub fn draw(&self, ctx: &mut Context, img: &Image) -> GameResult<()> {
@@ -58,9 +217,29 @@ impl Player {
         self.draw(ctx, a, Sprite::Player)
     }
     pub fn draw(&self, ctx: &mut Context, a: &Assets, sprite: Sprite) -> GameResult<()> {
+        let dir = angle_to_vec(self.obj.rot);
+        let side = Vector2::new(-dir.y, dir.x);
+        for (holstered, &(behind, aside, turn)) in self.inventory.slots.iter().zip(HOLSTERS.iter()) {
+            if let Some(wep) = holstered {
+                let dp = graphics::DrawParam {
+                    dest: self.obj.pos - behind * dir + aside * side,
+                    rotation: self.obj.rot + turn,
+                    scale: Point2::new(0.7, 0.7),
+                    .. self.obj.drawparams()
+                };
+                graphics::draw_ex(ctx, a.get_img(wep.weapon.entity_sprite), dp)?;
+            }
+        }
         if let Some(wep) = self.wep {
+            // Pull the weapon back while working its action and thrust it forward when striking
+            let mut pull = wep.cycling().map(|t| 4. * (t * PI).sin()).unwrap_or(0.);
+            if let Some(t) = self.striking() {
+                pull -= 6. * (t * PI).sin();
+            }
+            // Bring the weapon up while drawing it
+            pull += 10. * self.inventory.switching / SWITCH_TIME;
             let dp = graphics::DrawParam {
-                dest: self.obj.pos+angle_to_vec(self.obj.rot)*16.,
+                dest: self.obj.pos+dir*(16. - pull),
                 .. self.obj.drawparams()
             };
 
@@ -68,10 +247,29 @@ impl Player {
         }
         self.obj.draw(ctx, a.get_img(sprite))
     }
-    pub fn update(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<()> {
+    pub fn update(&mut self, events: &mut Vec<Event>) {
         if let Some(wep) = &mut self.wep {
-            wep.update(ctx, mplayer)?;
+            wep.update(events);
+        }
+        self.melee_cooldown = (self.melee_cooldown - DELTA).max(0.);
+        self.inventory.switching = (self.inventory.switching - DELTA).max(0.);
+    }
+    #[inline]
+    pub fn melee_ready(&self) -> bool {
+        self.melee_cooldown <= 0.
+    }
+    /// Starts a melee strike
+    #[inline]
+    pub fn swing(&mut self) {
+        self.melee_cooldown = MELEE_COOLDOWN;
+    }
+    /// How far through a strike it is, from `0` to `1`, if it is striking
+    pub fn striking(&self) -> Option<f32> {
+        let t = MELEE_COOLDOWN - self.melee_cooldown;
+        if self.melee_cooldown > 0. && t < STRIKE_TIME {
+            Some(t / STRIKE_TIME)
+        } else {
+            None
         }
-        Ok(())
     }
 }
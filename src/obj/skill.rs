@@ -0,0 +1,95 @@
+//! How well enemies shoot
+use std::f32::consts::PI;
+
+const DEG2RAD: f32 = PI / 180.;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/// The shooting skill of an enemy, stored with it in the level
+pub struct Skill {
+    /// Time between spotting the player and the first shot
+    pub reaction_time: f32,
+    /// Largest angle in radians a shot can miss the aim by
+    pub aim_error: f32,
+    /// Shots fired before pausing, `0` for no limit
+    pub burst_length: u16,
+    /// Time between bursts
+    pub burst_pause: f32,
+    /// How much of the weapon's spray is compensated, from `0` to `1`
+    pub spray_control: f32,
+}
+
+impl Skill {
+    pub const ROOKIE: Skill = Skill {
+        reaction_time: 0.8,
+        aim_error: 9. * DEG2RAD,
+        burst_length: 3,
+        burst_pause: 0.9,
+        spray_control: 0.,
+    };
+    pub const REGULAR: Skill = Skill {
+        reaction_time: 0.45,
+        aim_error: 5. * DEG2RAD,
+        burst_length: 5,
+        burst_pause: 0.6,
+        spray_control: 0.4,
+    };
+    pub const VETERAN: Skill = Skill {
+        reaction_time: 0.25,
+        aim_error: 2. * DEG2RAD,
+        burst_length: 8,
+        burst_pause: 0.35,
+        spray_control: 0.8,
+    };
+    /// The skill levels offered by the editor
+    pub const PRESETS: [Skill; 3] = [Skill::ROOKIE, Skill::REGULAR, Skill::VETERAN];
+}
+
+impl Default for Skill {
+    #[inline]
+    fn default() -> Self {
+        Skill::REGULAR
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Scales the skill of every enemy
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Default for Difficulty {
+    #[inline]
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+    /// The skill an enemy has at this difficulty
+    pub fn apply(self, skill: Skill) -> Skill {
+        // Factor for times and errors and for the control over the spray
+        let (slower, control) = match self {
+            Difficulty::Easy => (1.5, 0.5),
+            Difficulty::Normal => return skill,
+            Difficulty::Hard => (0.6, 1.5),
+        };
+        Skill {
+            reaction_time: skill.reaction_time * slower,
+            aim_error: skill.aim_error * slower,
+            burst_length: skill.burst_length,
+            burst_pause: skill.burst_pause * slower,
+            spray_control: (skill.spray_control * control).min(1.),
+        }
+    }
+}
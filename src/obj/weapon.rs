@@ -3,26 +3,36 @@ use std::fmt::{self, Display};
 
 use crate::{
     util::Point2,
-    game::DELTA,
+    game::{DELTA, sim::Event},
     io::{
-        snd::{Sound, MediaPlayer},
+        snd::Sound,
         tex::Sprite,
     },
 };
-use ggez::{Context, GameResult};
 
-use super::{Object, bullet::Bullet};
+use super::{Object, bullet::{Bullet, Owner}, grenade::Grenade, inventory::Slot, ammo::Calibre};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum FireMode {
     Automatic,
     SemiAutomatic,
     BoltAction,
     PumpAction{
+        /// Shells loaded at a time while reloading
         shell_load: u8,
-    }
+    },
+    /// Thrown by hand and explodes once the fuse runs out
+    Thrown{
+        /// Time from the throw until it explodes
+        fuse: f32,
+        /// Distance the explosion reaches, its damage falls off towards the edge
+        blast_radius: f32,
+    },
 }
 
+/// Time between a shot and working the action of a bolt or pump action weapon
+const ACTION_DELAY: f32 = 0.2;
+
 impl FireMode {
     #[inline]
     pub fn is_auto(self) -> bool {
@@ -32,21 +42,50 @@ impl FireMode {
             false
         }
     }
+    /// Whether the action has to be worked by hand after every shot
+    #[inline]
+    pub fn is_manual(self) -> bool {
+        match self {
+            FireMode::BoltAction | FireMode::PumpAction{..} => true,
+            _ => false,
+        }
+    }
+    #[inline]
+    pub fn is_thrown(self) -> bool {
+        if let FireMode::Thrown{..} = self {
+            true
+        } else {
+            false
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Deserialize)]
+/// A weapon as defined in `resources/weapons/weapons.ron`
 pub struct Weapon {
-    pub name: &'static str,
+    /// Identifier used to refer to the weapon in level files
+    pub id: String,
+    pub name: String,
+    /// Rounds in a clip, for thrown weapons the one in hand
     pub clip_size: NonZeroU16,
     pub clips: NonZeroU16,
+    /// The rounds it takes, shared with every weapon of the same calibre
+    pub calibre: Calibre,
+    /// Damage of each bullet, or of a blast at its center
     pub damage: f32,
-    /// Fraction of armour damage redirverted to hp damage
+    /// Fraction of armour damage redirected to hp damage
     pub penetration: f32,
+    #[serde(default)]
+    /// Bullets go through materials less dense than this
+    pub material_penetration: f32,
     /// Time between each shot
     pub fire_rate: f32,
     /// Time to reload a new clip/magazine
     pub reload_time: f32,
+    /// Bolt and pump actions have to be worked after every shot
     pub fire_mode: FireMode,
+    /// Where the player carries it, every slot holds one weapon
+    pub slot: Slot,
     pub shot_snd: Sound,
     pub cock_snd: Sound,
     pub reload_snd: Sound,
@@ -54,21 +93,54 @@ pub struct Weapon {
     pub impact_snd: Sound,
     pub entity_sprite: Sprite,
     pub hands_sprite: Sprite,
-    pub spray_pattern: &'static [f32],
+    /// Angles in radians the aim is jerked by for each consecutive shot
+    pub spray_pattern: Vec<f32>,
     pub spray_decay: f32,
     pub spray_repeat: usize,
+    /// Distance at which enemies hear shots, small for suppressed weapons
+    pub noise: f32,
+    #[serde(default)]
+    /// Whether bullets can glance off hard walls hit at a shallow angle
+    pub ricochets: bool,
+    #[serde(default = "one_pellet")]
+    /// Bullets fired per shot
+    pub pellets: u16,
+    #[serde(default)]
+    /// Angle in radians of the cone the pellets are spread evenly over
+    pub spread: f32,
 }
 
-mod consts;
-pub use self::consts::WEAPONS;
+#[inline]
+fn one_pellet() -> u16 {
+    1
+}
+
+mod data;
+pub use self::data::WEAPONS;
 
 impl Weapon {
+    /// Looks up a weapon by its identifier
+    pub fn by_id(id: &str) -> Option<&'static Weapon> {
+        WEAPONS.iter().find(|w| w.id == id)
+    }
+    /// How far in front of the character its shots start
+    ///
+    /// Thrown weapons leave from the character itself so they can't be thrown through walls.
+    #[inline]
+    pub fn muzzle_distance(&self) -> f32 {
+        if self.fire_mode.is_thrown() {
+            0.
+        } else {
+            20.
+        }
+    }
     pub fn make_instance(&self) -> WeaponInstance<'_> {
         let cur_clip = self.clip_size.get();
         WeaponInstance {
             weapon: self,
             cur_clip,
             loading_time: 0.,
+            reloading: false,
             jerk: 0.,
             jerk_decay: 0.,
             spray_index: 0,
@@ -105,6 +177,8 @@ pub struct WeaponInstance<'a> {
     pub cur_clip: u16,
     pub ammo: u16,
     pub loading_time: f32,
+    /// Whether the current loading time is for a reload
+    pub reloading: bool,
     pub jerk: f32,
     pub jerk_decay: f32,
     pub spray_index: usize,
@@ -132,6 +206,7 @@ impl<'a> WeaponInstance<'a> {
         let WeaponDrop{cur_clip, ammo, weapon, ..} = wd;
         Self {
             loading_time: 0.,
+            reloading: false,
             jerk: 0.,
             jerk_decay: 0.,
             spray_index: 0,
@@ -140,7 +215,7 @@ impl<'a> WeaponInstance<'a> {
             weapon,
         }
     }
-    pub fn update(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<()> {
+    pub fn update(&mut self, events: &mut Vec<Event>) {
         if self.jerk_decay <= DELTA {
             self.jerk = 0.;
             self.jerk_decay = 0.;
@@ -148,39 +223,89 @@ impl<'a> WeaponInstance<'a> {
         } else {
             self.jerk_decay -= DELTA;
         }
+        let mode = self.weapon.fire_mode;
         if self.loading_time <= DELTA {
             self.loading_time = 0.;
+            if self.reloading {
+                self.reloading = false;
+                // Pumps keep loading shells until they're full and are pumped once at the end
+                if let FireMode::PumpAction{..} = mode {
+                    if self.loads_more() {
+                        self.reload(events);
+                    } else {
+                        events.push(Event::Sound(self.weapon.cock_snd));
+                    }
+                }
+            }
         } else {
+            let before = self.loading_time;
             self.loading_time -= DELTA;
-            if self.loading_time <= DELTA {
-                mplayer.play(ctx, self.weapon.cock_snd)?;
+            let sound_at = if mode.is_manual() && !self.reloading {
+                // Work the action right after the shot
+                self.weapon.fire_rate - ACTION_DELAY
+            } else if let (FireMode::PumpAction{..}, true) = (mode, self.reloading) {
+                // Pumped once the last shell is in
+                -1.
+            } else {
+                DELTA
+            };
+            if before > sound_at && self.loading_time <= sound_at {
+                events.push(Event::Sound(self.weapon.cock_snd));
             }
         }
-        Ok(())
     }
-    pub fn reload(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<()> {
+    /// Whether a pump will load another round of shells after the current one
+    fn loads_more(&self) -> bool {
+        match self.weapon.fire_mode {
+            FireMode::PumpAction{..} => self.cur_clip < self.weapon.clip_size.get() && self.ammo > 0,
+            _ => false,
+        }
+    }
+    /// How far through working the action the weapon is, from `0` to `1`, if it is
+    pub fn cycling(&self) -> Option<f32> {
+        if self.weapon.fire_mode.is_manual() && !self.reloading && self.loading_time > 0. {
+            Some(1. - self.loading_time / self.weapon.fire_rate)
+        } else {
+            None
+        }
+    }
+    /// Starts loading the clip
+    ///
+    /// Pumps load `shell_load` shells at a time and play `reload_snd` for each round of them.
+    pub fn reload(&mut self, events: &mut Vec<Event>) {
         let clip_size = self.weapon.clip_size.get();
-        if self.cur_clip == clip_size || self.ammo == 0 {
-            return Ok(())
+        if self.cur_clip == clip_size || self.ammo == 0 || self.reloading {
+            return
         }
 
         self.loading_time = self.weapon.reload_time;
+        self.reloading = true;
 
-        let ammo_to_reload = self.weapon.clip_size.get() - self.cur_clip;
+        let mut ammo_to_reload = self.weapon.clip_size.get() - self.cur_clip;
+        if let FireMode::PumpAction{shell_load} = self.weapon.fire_mode {
+            ammo_to_reload = ammo_to_reload.min(u16::from(shell_load.max(1)));
+        }
 
         if self.ammo < ammo_to_reload {
             self.cur_clip += self.ammo;
             self.ammo = 0;
         } else {
             self.ammo -= ammo_to_reload;
-            self.cur_clip = clip_size;
+            self.cur_clip += ammo_to_reload;
         }
-        mplayer.play(ctx, self.weapon.reload_snd)
+        events.push(Event::Sound(self.weapon.reload_snd));
     }
-    pub fn shoot(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<Option<BulletMaker<'a>>> {
+    pub fn shoot(&mut self, events: &mut Vec<Event>) -> Option<BulletMaker<'a>> {
+        // Pumps can fire the shells they have loaded so far
+        if let (FireMode::PumpAction{..}, true) = (self.weapon.fire_mode, self.reloading) {
+            if self.cur_clip > 0 {
+                self.loading_time = 0.;
+                self.reloading = false;
+            }
+        }
         if self.cur_clip > 0 && self.loading_time == 0. {
             self.cur_clip -= 1;
-            if self.cur_clip > 0 {
+            if self.cur_clip > 0 || self.weapon.fire_mode.is_manual() {
                 self.loading_time = self.weapon.fire_rate;
             }
 
@@ -193,24 +318,47 @@ impl<'a> WeaponInstance<'a> {
                 self.spray_index -= self.weapon.spray_repeat;
             }
 
-            mplayer.play(ctx, self.weapon.shot_snd)?;
-            Ok(Some(BulletMaker(self.weapon, jerk)))
+            events.push(Event::Sound(self.weapon.shot_snd));
+            // The next grenade is readied right away
+            if self.weapon.fire_mode.is_thrown() {
+                self.reload(events);
+            }
+            Some(BulletMaker(self.weapon, jerk))
         } else {
             if self.cur_clip == 0 {
-                mplayer.play(ctx, self.weapon.click_snd)?;
+                events.push(Event::Sound(self.weapon.click_snd));
             }
-            Ok(None)
+            None
         }
     }
 }
 
+/// What leaves a weapon when it's fired
+pub enum Projectiles<'a> {
+    Bullets(Vec<Bullet<'a>>),
+    Grenade(Grenade<'a>),
+}
+
 pub struct BulletMaker<'a>(&'a Weapon, f32);
 impl<'a> BulletMaker<'a> {
-    pub fn make(self, mut obj: Object) -> Bullet<'a> {
-        obj.rot += self.1;
-        Bullet {
-            obj,
-            weapon: self.0
+    /// Makes the bullets of a shot or the thrown grenade
+    ///
+    /// Pellets are spread evenly over the weapon's cone.
+    pub fn make(self, mut obj: Object, owner: Owner) -> Projectiles<'a> {
+        let BulletMaker(weapon, jerk) = self;
+        obj.rot += jerk;
+
+        if weapon.fire_mode.is_thrown() {
+            return Projectiles::Grenade(Grenade::new(obj, weapon, owner));
+        }
+        let pellets = weapon.pellets.max(1);
+        if pellets == 1 {
+            return Projectiles::Bullets(vec![Bullet::new(obj, weapon, owner)]);
         }
+        let step = weapon.spread / f32::from(pellets - 1);
+        let first = obj.rot - 0.5 * weapon.spread;
+        Projectiles::Bullets((0..pellets)
+            .map(|i| Bullet::new(Object::with_rot(obj.pos, first + f32::from(i) * step), weapon, owner))
+            .collect())
     }
 }
@@ -0,0 +1,90 @@
+//! Loading the weapon definitions from `resources/weapons/weapons.ron`
+use std::{env, fs};
+use std::path::{Path, PathBuf};
+
+use super::{Weapon, FireMode};
+
+use ::ron;
+
+/// Where the weapon definitions are read from, inside the resources directory
+pub const WEAPONS_PATH: &str = "weapons/weapons.ron";
+/// The definitions shipped with the game, used if the file can't be read
+const BUILTIN_WEAPONS: &str = include_str!("../../../resources/weapons/weapons.ron");
+
+const DEG2RAD: f32 = std::f32::consts::PI / 180.;
+
+/// The resources directories the game mounts, so the file is found no matter the working directory
+///
+/// The workspace directory comes first when running with cargo, like in `main`.
+fn resource_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        dirs.push(Path::new(&manifest_dir).join("resources"));
+    }
+    if let Some(exe_dir) = env::current_exe().ok().as_ref().and_then(|exe| exe.parent()) {
+        dirs.push(exe_dir.join("resources"));
+    }
+    dirs
+}
+
+lazy_static! {
+    /// All weapons of the game in the order of the definition file
+    pub static ref WEAPONS: Vec<Weapon> = {
+        let path = resource_dirs()
+            .into_iter()
+            .map(|dir| dir.join(WEAPONS_PATH))
+            .find(|path| path.is_file());
+        let loaded = match &path {
+            Some(path) => fs::read_to_string(path)
+                .map_err(|e| format!("{}", e))
+                .and_then(|text| parse_weapons(&text)),
+            None => Err("no resources directory has it".to_owned()),
+        };
+        match loaded {
+            Ok(weapons) => weapons,
+            Err(e) => {
+                let shown = path.unwrap_or_else(|| PathBuf::from(WEAPONS_PATH));
+                eprintln!("Warning: couldn't load {}: {}\nUsing the built-in weapons", shown.display(), e);
+                parse_weapons(BUILTIN_WEAPONS).expect("built-in weapon definitions are invalid")
+            }
+        }
+    };
+}
+
+/// Parses and validates a list of weapon definitions
+pub fn parse_weapons(text: &str) -> Result<Vec<Weapon>, String> {
+    let mut weapons: Vec<Weapon> = ron::de::from_str(text).map_err(|e| format!("{:?}", e))?;
+    if weapons.is_empty() {
+        return Err("no weapons defined".to_owned());
+    }
+
+    for i in 0..weapons.len() {
+        let (defined, rest) = weapons.split_at_mut(i);
+        let weapon = &mut rest[0];
+
+        if defined.iter().any(|w| w.id == weapon.id) {
+            return Err(format!("{} is defined twice", weapon.id));
+        }
+        let len = weapon.spray_pattern.len();
+        if len == 0 {
+            return Err(format!("{} has an empty spray pattern", weapon.id));
+        }
+        if weapon.spray_repeat == 0 || weapon.spray_repeat > len {
+            return Err(format!("{} has to repeat between 1 and {} spray steps", weapon.id, len));
+        }
+        if let FireMode::Thrown{fuse, blast_radius} = weapon.fire_mode {
+            if fuse <= 0. || blast_radius <= 0. {
+                return Err(format!("{} needs a positive fuse and blast radius", weapon.id));
+            }
+        }
+        if weapon.pellets == 0 {
+            return Err(format!("{} has to fire at least one pellet", weapon.id));
+        }
+        for angle in &mut weapon.spray_pattern {
+            *angle *= DEG2RAD;
+        }
+        weapon.spread *= DEG2RAD;
+    }
+
+    Ok(weapons)
+}